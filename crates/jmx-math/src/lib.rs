@@ -0,0 +1,260 @@
+//! Fixed point math shared by the jmx instructions, with no Solana dependency so clients
+//! and tests can price pool actions exactly like the program does.
+//!
+//! USD values and prices are normalized with PRICE_DECIMALS, token amounts are in the
+//! smallest unit of their mint. Products are taken in u128 and every division states
+//! which way it rounds.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+pub const PRICE_DECIMALS: u8 = 6;
+pub const PRICE_DECIMALS_FACTOR: u64 = 1_000_000;
+pub const BASIS_POINTS_PRECISION: u64 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MathError {
+	Overflow,
+	DivideByZero,
+}
+
+impl fmt::Display for MathError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MathError::Overflow => write!(f, "math operation overflowed"),
+			MathError::DivideByZero => write!(f, "division by zero"),
+		}
+	}
+}
+
+impl std::error::Error for MathError {}
+
+pub type Result<T> = std::result::Result<T, MathError>;
+
+/// Direction of a division, values owed by the pool round down and values owed to it round up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+	Down,
+	Up,
+}
+
+fn div_u128(numerator: u128, denominator: u128, rounding: Rounding) -> Result<u128> {
+	if denominator == 0 {
+		return Err(MathError::DivideByZero);
+	}
+	let quotient = numerator / denominator;
+	let remainder = numerator % denominator;
+	match rounding {
+		Rounding::Up if remainder > 0 => quotient.checked_add(1).ok_or(MathError::Overflow),
+		_ => Ok(quotient),
+	}
+}
+
+fn to_u64(value: u128) -> Result<u64> {
+	u64::try_from(value).map_err(|_| MathError::Overflow)
+}
+
+/// a * b / c with a u128 intermediate
+pub fn mul_div(a: u64, b: u64, c: u64, rounding: Rounding) -> Result<u64> {
+	to_u64(div_u128((a as u128) * (b as u128), c as u128, rounding)?)
+}
+
+/// 10^exponent, the scale of an oracle exponent or of the decimals of a mint
+pub fn pow10(exponent: u32) -> Result<u64> {
+	10_u64.checked_pow(exponent).ok_or(MathError::Overflow)
+}
+
+/// basis_points / BASIS_POINTS_PRECISION of the amount
+pub fn apply_basis_points(amount: u64, basis_points: u64, rounding: Rounding) -> Result<u64> {
+	mul_div(amount, basis_points, BASIS_POINTS_PRECISION, rounding)
+}
+
+/// a * b / c scaled by basis_points / BASIS_POINTS_PRECISION, in a single u128 division
+pub fn mul_div_basis_points(a: u64, b: u64, c: u64, basis_points: u64, rounding: Rounding) -> Result<u64> {
+	let numerator = ((a as u128) * (b as u128))
+		.checked_mul(basis_points as u128)
+		.ok_or(MathError::Overflow)?;
+	to_u64(div_u128(numerator, (c as u128) * (BASIS_POINTS_PRECISION as u128), rounding)?)
+}
+
+/// A USD value normalized with PRICE_DECIMALS
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Usd(pub u64);
+
+/// The USD price of one whole token normalized with PRICE_DECIMALS
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Price(pub u64);
+
+/// An amount of a token in the smallest unit of its mint
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TokenAmount(pub u64);
+
+impl Usd {
+	pub const ZERO: Usd = Usd(0);
+
+	pub fn checked_add(self, other: Usd) -> Result<Usd> {
+		self.0.checked_add(other.0).map(Usd).ok_or(MathError::Overflow)
+	}
+
+	pub fn checked_sub(self, other: Usd) -> Result<Usd> {
+		self.0.checked_sub(other.0).map(Usd).ok_or(MathError::Overflow)
+	}
+
+	pub fn saturating_sub(self, other: Usd) -> Usd {
+		Usd(self.0.saturating_sub(other.0))
+	}
+
+	pub fn abs_diff(self, other: Usd) -> Usd {
+		if self > other { Usd(self.0 - other.0) } else { Usd(other.0 - self.0) }
+	}
+
+	/// The amount of a token with token_decimals worth this value at price
+	pub fn to_tokens(self, price: Price, token_decimals: u8, rounding: Rounding) -> Result<TokenAmount> {
+		to_u64(div_u128(
+			(self.0 as u128) * (pow10(token_decimals as u32)? as u128),
+			price.0 as u128,
+			rounding
+		)?).map(TokenAmount)
+	}
+}
+
+impl Price {
+	/// Normalizes an oracle price in units of 10^-exponent USD, rounding down
+	pub fn from_oracle(price: u64, exponent: u32) -> Result<Price> {
+		to_u64(div_u128(
+			(price as u128) * (PRICE_DECIMALS_FACTOR as u128),
+			10_u128.checked_pow(exponent).ok_or(MathError::Overflow)?,
+			Rounding::Down
+		)?).map(Price)
+	}
+
+	/// The average of this price over size and other over other_size, rounding down
+	pub fn weighted_average(self, size: u64, other: Price, other_size: u64) -> Result<Price> {
+		let total_size = size.checked_add(other_size).ok_or(MathError::Overflow)?;
+		let numerator = ((self.0 as u128) * (size as u128))
+			.checked_add((other.0 as u128) * (other_size as u128))
+			.ok_or(MathError::Overflow)?;
+		to_u64(div_u128(numerator, total_size as u128, Rounding::Down)?).map(Price)
+	}
+
+	/// The average price of what is left of size at this average once removed_size at removed
+	/// is taken out of it, rounding down and stopping at zero
+	pub fn average_without(self, size: u64, removed: Price, removed_size: u64) -> Result<Price> {
+		let remaining_size = size.checked_sub(removed_size).ok_or(MathError::Overflow)?;
+		let numerator = ((self.0 as u128) * (size as u128))
+			.saturating_sub((removed.0 as u128) * (removed_size as u128));
+		to_u64(div_u128(numerator, remaining_size as u128, Rounding::Down)?).map(Price)
+	}
+}
+
+impl TokenAmount {
+	pub fn checked_add(self, other: TokenAmount) -> Result<TokenAmount> {
+		self.0.checked_add(other.0).map(TokenAmount).ok_or(MathError::Overflow)
+	}
+
+	pub fn checked_sub(self, other: TokenAmount) -> Result<TokenAmount> {
+		self.0.checked_sub(other.0).map(TokenAmount).ok_or(MathError::Overflow)
+	}
+
+	/// The value at price of this amount of a token with token_decimals
+	pub fn to_usd(self, price: Price, token_decimals: u8, rounding: Rounding) -> Result<Usd> {
+		mul_div(self.0, price.0, pow10(token_decimals as u32)?, rounding).map(Usd)
+	}
+
+	/// The amount of another token worth the same at to_price, converted in one division so
+	/// the value is not truncated to PRICE_DECIMALS on the way
+	pub fn convert(
+		self,
+		price: Price,
+		token_decimals: u8,
+		to_price: Price,
+		to_token_decimals: u8,
+		rounding: Rounding
+	) -> Result<TokenAmount> {
+		let to_decimals_factor = pow10(to_token_decimals as u32)? as u128;
+		let numerator = (self.0 as u128)
+			.checked_mul(price.0 as u128)
+			.and_then(|value| value.checked_mul(to_decimals_factor))
+			.ok_or(MathError::Overflow)?;
+		let denominator = (to_price.0 as u128)
+			.checked_mul(pow10(token_decimals as u32)? as u128)
+			.ok_or(MathError::Overflow)?;
+		to_u64(div_u128(numerator, denominator, rounding)?).map(TokenAmount)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn mul_div_rounds_in_the_requested_direction() {
+		assert_eq!(mul_div(10, 10, 3, Rounding::Down).unwrap(), 33);
+		assert_eq!(mul_div(10, 10, 3, Rounding::Up).unwrap(), 34);
+		assert_eq!(mul_div(10, 9, 3, Rounding::Up).unwrap(), 30);
+		assert_eq!(mul_div(u64::MAX, 2, 2, Rounding::Down).unwrap(), u64::MAX);
+		assert_eq!(mul_div(u64::MAX, 2, 1, Rounding::Down), Err(MathError::Overflow));
+		assert_eq!(mul_div(1, 1, 0, Rounding::Down), Err(MathError::DivideByZero));
+	}
+
+	#[test]
+	fn oracle_prices_are_normalized() {
+		assert_eq!(Price::from_oracle(1_000_000, 4).unwrap(), Price(100_000_000));
+		assert_eq!(Price::from_oracle(1_000_000, 6).unwrap(), Price(1_000_000));
+		assert_eq!(Price::from_oracle(123_456_789, 8).unwrap(), Price(1_234_567));
+		assert_eq!(Price::from_oracle(1, 40), Err(MathError::Overflow));
+	}
+
+	#[test]
+	fn token_amounts_are_valued_with_their_decimals() {
+		let price = Price(25 * PRICE_DECIMALS_FACTOR);
+		assert_eq!(TokenAmount(2_000_000_000).to_usd(price, 9, Rounding::Down).unwrap(), Usd(50 * PRICE_DECIMALS_FACTOR));
+		assert_eq!(TokenAmount(1).to_usd(price, 9, Rounding::Down).unwrap(), Usd(0));
+		assert_eq!(TokenAmount(1).to_usd(price, 9, Rounding::Up).unwrap(), Usd(1));
+		assert_eq!(TokenAmount(3).to_usd(price, 0, Rounding::Down).unwrap(), Usd(75 * PRICE_DECIMALS_FACTOR));
+	}
+
+	#[test]
+	fn usd_converts_back_to_tokens() {
+		let price = Price(3 * PRICE_DECIMALS_FACTOR);
+		assert_eq!(Usd(PRICE_DECIMALS_FACTOR).to_tokens(price, 6, Rounding::Down).unwrap(), TokenAmount(333_333));
+		assert_eq!(Usd(PRICE_DECIMALS_FACTOR).to_tokens(price, 6, Rounding::Up).unwrap(), TokenAmount(333_334));
+		assert_eq!(Usd(1).to_tokens(Price(0), 6, Rounding::Down), Err(MathError::DivideByZero));
+	}
+
+	#[test]
+	fn converts_between_tokens_with_different_decimals() {
+		// 2 SOL with 9 decimals at $25 is 50 USDC with 6 decimals
+		let sol = TokenAmount(2_000_000_000);
+		let sol_price = Price(25 * PRICE_DECIMALS_FACTOR);
+		let usdc_price = Price(PRICE_DECIMALS_FACTOR);
+		assert_eq!(sol.convert(sol_price, 9, usdc_price, 6, Rounding::Down).unwrap(), TokenAmount(50_000_000));
+		assert_eq!(TokenAmount(50_000_000).convert(usdc_price, 6, sol_price, 9, Rounding::Down).unwrap(), sol);
+		// a lamport is worth less than a USDC unit but is not lost to a USD intermediate
+		assert_eq!(TokenAmount(3).convert(sol_price, 9, usdc_price, 9, Rounding::Down).unwrap(), TokenAmount(75));
+		assert_eq!(TokenAmount(1).convert(sol_price, 9, usdc_price, 6, Rounding::Up).unwrap(), TokenAmount(1));
+	}
+
+	#[test]
+	fn basis_points_and_usd_arithmetic() {
+		assert_eq!(apply_basis_points(10_001, 30, Rounding::Down).unwrap(), 30);
+		assert_eq!(apply_basis_points(10_001, 30, Rounding::Up).unwrap(), 31);
+		assert_eq!(mul_div_basis_points(10, 10, 3, 9_970, Rounding::Down).unwrap(), 33);
+		// the products do not have to fit in a u64
+		assert_eq!(mul_div_basis_points(u64::MAX, 1_000_000, 1_000_000, 9_970, Rounding::Down).unwrap(), 18_391_403_841_488_422_960);
+		assert_eq!(mul_div_basis_points(u64::MAX, 1_000_000, 1_000_000, 10_030, Rounding::Down), Err(MathError::Overflow));
+		assert_eq!(Usd(5).abs_diff(Usd(8)), Usd(3));
+		assert_eq!(Usd(5).saturating_sub(Usd(8)), Usd::ZERO);
+		assert_eq!(Usd(5).checked_sub(Usd(8)), Err(MathError::Overflow));
+		assert_eq!(TokenAmount(u64::MAX).checked_add(TokenAmount(1)), Err(MathError::Overflow));
+	}
+
+	#[test]
+	fn prices_are_averaged_over_sizes() {
+		assert_eq!(Price(100).weighted_average(10, Price(200), 30).unwrap(), Price(175));
+		assert_eq!(Price(175).average_without(40, Price(200), 30).unwrap(), Price(100));
+		assert_eq!(Price(u64::MAX).weighted_average(u64::MAX, Price(u64::MAX), 1), Err(MathError::Overflow));
+		assert_eq!(Price(u64::MAX).weighted_average(u64::MAX - 1, Price(0), 1).unwrap(), Price(u64::MAX - 1));
+		assert_eq!(Price(100).average_without(10, Price(100), 10), Err(MathError::DivideByZero));
+	}
+}
@@ -1,27 +1,32 @@
 use anchor_lang::constant;
+use anchor_lang::prelude::Pubkey;
+use solana_program::pubkey;
 
+/// the pyth program that owns the price accounts used as oracles
+#[cfg(not(feature = "devnet"))]
+pub const PYTH_PROGRAM_ID: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+#[cfg(feature = "devnet")]
+pub const PYTH_PROGRAM_ID: Pubkey = pubkey!("gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s");
 #[constant]
 pub const EXCHANGE_INFO_SEED: &str = "exchange-info";
 #[constant]
 pub const EXCHANGE_AUTHORITY_SEED: &str = "exchange-authority";
 #[constant]
 pub const LP_MINT_SEED: &str = "lp-mint";
+/// the first LP minted is worth 1 USD per whole LP token
 #[constant]
-pub const SMALL_UINTS_IN_EXCHANGE: u8 = 8;
+pub const LP_DECIMALS: u8 = 8;
+#[constant]
+pub const SMALL_UINTS_IN_EXCHANGE: u8 = 10;
 #[constant]
 pub const AVAILABLE_ASSET_SPACE: u16 = 624;
 #[constant]
 pub const BASIS_POINTS_PRECISION: u64 = 10_000;
+/// upper bound for every fee set through set_fees
 #[constant]
-pub const FEE_IN_BASIS_POINTS: u64 = 10_030;
-#[constant]
-pub const FEE_RAW: u64 = 30;
-#[constant]
-pub const MAIN_ASSET_SWAP_FEE_IN_BASIS_POINTS: u16 = 8;
+pub const MAX_FEE_BASIS_POINTS: u64 = 500;
 #[constant]
-pub const STABLE_ASSET_SWAP_FEE_IN_BASIS_POINTS: u16 = 4;
-#[constant]
-pub const PENALTY_IN_BASIS_POINTS: u16 = 30;
+pub const MAX_LIQUIDATION_FEE_USD: u64 = 100;
 #[constant]
 pub const PRICE_DECIMALS: u8 = 6;
 #[constant]
@@ -29,3 +34,22 @@ pub const PRICE_DECIMALS_FACTOR: u64 = 1_000_000;
 /// 8 hours
 #[constant]
 pub const FUNDING_INTERVAL: u16 = 8 * 60 * 60;
+#[constant]
+pub const MAX_LEVERAGE: u64 = 50;
+/// funding rate charged per FUNDING_INTERVAL at full utilization of the pool reserves
+#[constant]
+pub const FUNDING_RATE_FACTOR: u64 = 100;
+#[constant]
+pub const FUNDING_RATE_PRECISION: u64 = 1_000_000;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn math_constants_match_jmx_math() {
+		assert_eq!(PRICE_DECIMALS, jmx_math::PRICE_DECIMALS);
+		assert_eq!(PRICE_DECIMALS_FACTOR, jmx_math::PRICE_DECIMALS_FACTOR);
+		assert_eq!(BASIS_POINTS_PRECISION, jmx_math::BASIS_POINTS_PRECISION);
+	}
+}
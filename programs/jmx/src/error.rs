@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+	#[msg("Math operation overflowed")]
+	MathOverflow,
+	#[msg("Invalid oracle account provided")]
+	InvalidOracle,
+	#[msg("Oracle price is stale")]
+	StalePrice,
+	#[msg("Oracle price is not trading")]
+	PriceNotTrading,
+	#[msg("Oracle price confidence is too wide")]
+	PriceConfidenceTooWide,
+	#[msg("Output is below the minimum accepted amount")]
+	SlippageExceeded,
+	#[msg("Not enough available pool reserves")]
+	InsufficientReserves,
+	#[msg("Signer is not the exchange admin")]
+	UnauthorizedAdmin,
+	#[msg("Signer is not the pending exchange admin")]
+	UnauthorizedPendingAdmin,
+	#[msg("Signer does not hold the role required for this instruction")]
+	UnauthorizedRole,
+	#[msg("Asset is not whitelisted on the exchange")]
+	AssetNotWhitelisted,
+	#[msg("Must supply all whitelisted assets as remaining accounts")]
+	MissingWhitelistedAssets,
+	#[msg("Account is not owned by the token program")]
+	InvalidTokenAccount,
+	#[msg("Available asset does not match the mint")]
+	InvalidAvailableAsset,
+	#[msg("Bump not found for the exchange authority")]
+	InvalidBump,
+	#[msg("Too few lamports for transaction")]
+	AmountTooSmall,
+	#[msg("Not enough lp token exists")]
+	InsufficientLpSupply,
+	#[msg("Invalid collateral for the position")]
+	InvalidCollateral,
+	#[msg("Asset is not shortable")]
+	AssetNotShortable,
+	#[msg("Invalid size or collateral delta for the position")]
+	InvalidPositionDelta,
+	#[msg("Position has no collateral")]
+	PositionHasNoCollateral,
+	#[msg("Position collateral exceeds size")]
+	CollateralExceedsSize,
+	#[msg("Position exceeds max leverage")]
+	MaxLeverageExceeded,
+	#[msg("Position losses or fees exceed collateral, position must be liquidated")]
+	PositionMustBeLiquidated,
+	#[msg("Position cannot be liquidated")]
+	PositionNotLiquidatable,
+	#[msg("Fee exceeds the max fee")]
+	InvalidFee,
+	#[msg("Token account is not the treasury token account for the asset")]
+	InvalidTreasuryAccount,
+	#[msg("Asset is withdraw only")]
+	AssetWithdrawOnly,
+	#[msg("Exchange is paused")]
+	ExchangePaused,
+	#[msg("Action is paused for the asset")]
+	ActionPaused,
+	#[msg("Asset must be withdraw only before it is removed")]
+	AssetNotWithdrawOnly,
+	#[msg("Asset still has reserves or open positions")]
+	AssetHasReserves,
+	#[msg("Deposit exceeds the max reserves of the asset")]
+	MaxReservesExceeded,
+	#[msg("Deposit exceeds the max usd debt of the asset")]
+	MaxUsdDebtExceeded,
+	#[msg("Token decimals do not match the decimals of the mint")]
+	InvalidTokenDecimals,
+	#[msg("Input is above the maximum accepted amount")]
+	MaxInputExceeded,
+	#[msg("Available asset is listed on another exchange")]
+	InvalidAssetExchange,
+	#[msg("Asset is passed more than once")]
+	DuplicateAsset,
+	#[msg("Stable and shortable flags can not change while the asset has open positions or debt")]
+	AssetInUse,
+	#[msg("Input and output assets of a swap must be different")]
+	SameSwapAsset,
+}
+
+impl From<jmx_math::MathError> for ErrorCode {
+	fn from(_: jmx_math::MathError) -> Self {
+		ErrorCode::MathOverflow
+	}
+}
@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+
+// prices are oracle prices in units of 10^-exponent USD unless they are named normalized,
+// normalized prices and usd amounts are normalized with PRICE_DECIMALS
+
+#[event]
+pub struct LpMinted {
+	pub user: Pubkey,
+	pub mint: Pubkey,
+	pub amount_in: u64,
+	pub lp_amount: u64,
+	pub price: u64,
+	pub exponent: u64,
+	pub fee_basis_points: u64,
+	pub aum: u64,
+	pub pool_reserves: u64,
+	pub fee_reserves: u64,
+}
+
+#[event]
+pub struct LpBurned {
+	pub user: Pubkey,
+	pub mint: Pubkey,
+	pub lp_amount: u64,
+	pub amount_out: u64,
+	pub price: u64,
+	pub exponent: u64,
+	pub fee_basis_points: u64,
+	pub aum: u64,
+	pub pool_reserves: u64,
+	pub fee_reserves: u64,
+}
+
+#[event]
+pub struct Swapped {
+	pub user: Pubkey,
+	pub input_mint: Pubkey,
+	pub output_mint: Pubkey,
+	pub amount_in: u64,
+	pub amount_out: u64,
+	pub input_price: u64,
+	pub input_exponent: u64,
+	pub output_price: u64,
+	pub output_exponent: u64,
+	pub fee_basis_points: u64,
+	pub input_pool_reserves: u64,
+	pub output_pool_reserves: u64,
+	pub output_fee_reserves: u64,
+}
+
+#[event]
+pub struct PositionIncreased {
+	pub owner: Pubkey,
+	pub position: Pubkey,
+	pub mint: Pubkey,
+	pub collateral_mint: Pubkey,
+	pub is_long: bool,
+	pub collateral_amount: u64,
+	pub size_delta: u64,
+	pub normalized_price: u64,
+	pub fee_usd: u64,
+	pub size: u64,
+	pub collateral: u64,
+	pub average_price: u64,
+	pub reserve_amount: u64,
+	pub pool_reserves: u64,
+	pub occupied_reserves: u64,
+}
+
+#[event]
+pub struct PositionDecreased {
+	pub owner: Pubkey,
+	pub position: Pubkey,
+	pub mint: Pubkey,
+	pub collateral_mint: Pubkey,
+	pub is_long: bool,
+	pub collateral_delta: u64,
+	pub size_delta: u64,
+	pub normalized_price: u64,
+	pub fee_usd: u64,
+	pub has_profit: bool,
+	pub realized_delta: u64,
+	pub amount_out: u64,
+	pub size: u64,
+	pub collateral: u64,
+	pub pool_reserves: u64,
+	pub occupied_reserves: u64,
+}
+
+#[event]
+pub struct Liquidated {
+	pub owner: Pubkey,
+	pub position: Pubkey,
+	pub liquidator: Pubkey,
+	pub mint: Pubkey,
+	pub collateral_mint: Pubkey,
+	pub is_long: bool,
+	pub size: u64,
+	pub collateral: u64,
+	pub normalized_price: u64,
+	pub margin_fees: u64,
+	pub liquidator_amount: u64,
+	pub pool_reserves: u64,
+	pub occupied_reserves: u64,
+}
+
+#[event]
+pub struct FundingUpdated {
+	pub mint: Pubkey,
+	pub cumulative_funding_rate: u64,
+	pub last_funding_time: u64,
+}
+
+#[event]
+pub struct AssetListed {
+	pub mint: Pubkey,
+	pub oracle: Pubkey,
+	pub backup_oracle: Pubkey,
+	pub token_weight: u64,
+	pub total_weights: u64,
+	pub stable_token: bool,
+	pub shortable_token: bool,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+	pub mint: Pubkey,
+	pub treasury_amount: u64,
+	pub lp_amount: u64,
+	pub pool_reserves: u64,
+}
+
+#[event]
+pub struct FeesChanged {
+	pub tax_basis_points: u64,
+	pub stable_tax_basis_points: u64,
+	pub mint_burn_basis_points: u64,
+	pub swap_fee_basis_points: u64,
+	pub stable_swap_fee_basis_points: u64,
+	pub margin_fee_basis_points: u64,
+	pub liquidation_fee_usd: u64,
+	pub min_profit_time: u64,
+}
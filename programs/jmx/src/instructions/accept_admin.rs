@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use crate::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String)]
+pub struct AcceptAdmin<'info> {
+	#[account(
+		constraint = pending_admin.key() == exchange.pending_admin @ ErrorCode::UnauthorizedPendingAdmin
+	)]
+	pub pending_admin: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+}
+
+// second step of an admin transfer, the roles are left as they are and
+// are handed over separately through set_roles
+pub fn handler(ctx: Context<AcceptAdmin>, _exchange_name: String) -> ProgramResult {
+	let exchange = &mut ctx.accounts.exchange;
+	exchange.admin = ctx.accounts.pending_admin.key();
+	exchange.pending_admin = Pubkey::default();
+	verbose_msg!("new admin {}", exchange.admin);
+	Ok(())
+}
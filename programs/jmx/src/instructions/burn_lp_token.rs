@@ -2,7 +2,10 @@ use anchor_spl::token::{Mint, Token, TokenAccount};
 use anchor_lang::prelude::*;
 use crate::constants::*;
 use crate::*;
-use mint_lp_token::{calculate_aum, calculate_fee_basis_points};
+use crate::error::ErrorCode;
+use mint_lp_token::{calculate_aum, calculate_fee_basis_points, decrease_usd_debt, get_token_amount, ReserveChange};
+use crate::events::LpBurned;
+use jmx_math::{mul_div, mul_div_basis_points, Rounding};
 
 // need to check that the mint provided matches the redeemable mint
 // CHECK: that mints and provided assets match for all provided accounts
@@ -55,64 +58,34 @@ pub struct BurnLpToken<'info> {
 }
 
 // CHECK: need to check that oracle account provided matches oracle account in available asset
-pub fn handler(ctx: Context<BurnLpToken>, exchange_name: String, asset_name: String, lamports: u64) -> ProgramResult {
-	assert!(lamports > 100, "too few lamports for transaction");
-	assert!(
-		ctx.remaining_accounts.len() / 2 == ctx.accounts.exchange.assets.len(), 
-		"must supply all whitelisted assets when burning"
-	);
-	assert!(ctx.accounts.lp_mint.supply > lamports, "not enough lp token exists");
-
-	// transfer lamports from user to reserve_asset_token_acount
-	let exchange_reserve_token = &ctx.accounts.exchange_reserve_token;
-	msg!("lamports {}", lamports);
+pub fn handler(ctx: Context<BurnLpToken>, exchange_name: String, asset_name: String, lamports: u64, min_reserve_out: u64) -> ProgramResult {
+	validate_burn_lp(
+		&ctx.accounts.exchange,
+		&ctx.accounts.available_asset,
+		ctx.remaining_accounts,
+		ctx.accounts.lp_mint.supply,
+		lamports
+	)?;
+	verbose_msg!("lamports {}", lamports);
 
-	let (aum, precise_price, exponent) = calculate_aum(
+	// burns are priced at the min aum and the withdrawal at the max price so leaving LPs cannot drain the remaining ones
+	let (aum, _, oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
 		ctx.remaining_accounts, 
-		exchange_reserve_token,
-		&ctx.accounts.exchange.price_oracles
-	).unwrap();
-
-	msg!("about to log lp_mint");
-	let lp_mint = &ctx.accounts.lp_mint;
-	msg!("lp_mint {:?}", lp_mint.key());
+		&ctx.accounts.exchange_reserve_token
+	)?;
+	verbose_msg!("lp_mint {:?}", ctx.accounts.lp_mint.key());
 
-	let lp_mint_supply = lp_mint.supply;
-	let total_fee_in_basis_points = calculate_fee_basis_points(
+	let quote = apply_burn_lp(
+		&ctx.accounts.exchange,
+		&mut ctx.accounts.available_asset,
+		ctx.accounts.lp_mint.supply,
 		aum,
-		&ctx.accounts.available_asset,
-		ctx.accounts.exchange.total_weights,
-		precise_price,
-		exponent,
-		lamports,
-		false
-	);
-	msg!("FEE_IN_BASIS_POINTS {}", FEE_IN_BASIS_POINTS);
-	msg!("total_fee_in_basis_points {}", total_fee_in_basis_points);
-	let price_per_lp_token_numerator = aum.checked_mul(total_fee_in_basis_points as u64)
-		.unwrap();
-
-	let price_per_lp_token_denominator = lp_mint_supply.checked_mul(BASIS_POINTS_PRECISION as u64)
-			.unwrap();
-	
-	msg!("precise_price {}", precise_price);
-	let usd_value_of_burn = lamports.
-		checked_mul(price_per_lp_token_numerator).
-		unwrap().
-		checked_div(price_per_lp_token_denominator).
-		unwrap();
-
-	msg!("usd_value_of_burn {}", usd_value_of_burn);
-	
-	let burn_value_to_reserve_amount = usd_value_of_burn.
-		checked_mul(10_u128.pow(exponent as u32) as u64).
-		unwrap().
-		checked_div(precise_price)
-		.unwrap();
-	
-	msg!("numerator price_per_lp_token_numerator {}", price_per_lp_token_numerator);
-	msg!("denom price_per_lp_token_denominator {}", price_per_lp_token_denominator);
-	msg!("burn_value_to_reserve_amount {}", burn_value_to_reserve_amount);
+		oracle_price.max_price()?,
+		oracle_price.exponent,
+		lamports
+	)?;
+	require!(quote.amount_out >= min_reserve_out, ErrorCode::SlippageExceeded);
 
 	let exchange_auth_bump = match ctx.bumps.get("exchange_authority") {
 			Some(bump) => {
@@ -120,18 +93,10 @@ pub fn handler(ctx: Context<BurnLpToken>, exchange_name: String, asset_name: Str
 			}
 			None => {
 					msg!("Wrong bump key. Available keys are {:?}", ctx.bumps.keys());
-					panic!("Wrong bump key")
+					return Err(ErrorCode::InvalidBump.into());
 			}
 	};
 
-	let transfer_reserve_amount = burn_value_to_reserve_amount.
-	checked_mul(BASIS_POINTS_PRECISION as u64).
-	unwrap().
-	checked_div(total_fee_in_basis_points).
-	unwrap();
-
-	msg!("transfer_reserve_amount {}", transfer_reserve_amount);
-
 	let exchange_name = ctx.accounts.exchange.name.as_ref();
 	let seeds = exchange_authority_seeds!(
 			exchange_name = exchange_name,
@@ -143,16 +108,124 @@ pub fn handler(ctx: Context<BurnLpToken>, exchange_name: String, asset_name: Str
 
 	token::transfer(
 		ctx.accounts.into_transfer_context(signer),
-		transfer_reserve_amount as u64,
+		quote.amount_out,
 	)?;
 
-	let asset = &mut ctx.accounts.available_asset;
-	asset.pool_reserves -= burn_value_to_reserve_amount;
-	asset.fee_reserves += burn_value_to_reserve_amount - transfer_reserve_amount;
-	msg!("pool reserves {} fee reserves {}", asset.pool_reserves, asset.fee_reserves);
+	emit!(LpBurned {
+		user: ctx.accounts.user_authority.key(),
+		mint: ctx.accounts.available_asset.mint_address,
+		lp_amount: lamports,
+		amount_out: quote.amount_out,
+		price: quote.price,
+		exponent: quote.exponent,
+		fee_basis_points: quote.fee_basis_points,
+		aum: quote.aum,
+		pool_reserves: quote.pool_reserves,
+		fee_reserves: quote.fee_reserves,
+	});
 	Ok(())
 }
 
+/// Result of a burn, returned as return data by quote_burn_lp. Prices are the oracle price
+/// in units of 10^-exponent USD and the reserves are the ones after the burn
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct BurnLpQuote {
+	pub amount_out: u64,
+	pub fee_basis_points: u64,
+	pub price: u64,
+	pub exponent: u64,
+	pub aum: u64,
+	pub pool_reserves: u64,
+	pub fee_reserves: u64,
+}
+
+pub fn validate_burn_lp(
+	exchange: &Exchange,
+	available_asset: &AvailableAsset,
+	remaining_accounts: &[AccountInfo],
+	lp_mint_supply: u64,
+	lamports: u64
+) -> Result<()> {
+	require!(!exchange.paused, ErrorCode::ExchangePaused);
+	require!(!available_asset.burn_paused, ErrorCode::ActionPaused);
+	require!(lamports > 100, ErrorCode::AmountTooSmall);
+	require!(exchange.assets.contains(&available_asset.mint_address), ErrorCode::AssetNotWhitelisted);
+	require!(
+		remaining_accounts.len() / 4 == exchange.assets.len(), 
+		ErrorCode::MissingWhitelistedAssets
+	);
+	require!(lp_mint_supply > lamports, ErrorCode::InsufficientLpSupply);
+	Ok(())
+}
+
+/// Prices a burn of lamports of the LP token against the aum and the lp supply, paying out the
+/// asset at precise_price from the pool reserves and keeping the fee in the fee reserves.
+/// burn_lp_token applies it to the account, quote_burn_lp to a copy of it
+pub fn apply_burn_lp(
+	exchange: &Exchange,
+	available_asset: &mut AvailableAsset,
+	lp_mint_supply: u64,
+	aum: u64,
+	precise_price: u64,
+	exponent: u64,
+	lamports: u64
+) -> Result<BurnLpQuote> {
+	// the fee is set by the reserves left after the burn, so it is taken on the amount of the
+	// asset the LP tokens redeem before fees rather than on the LP amount
+	let redemption_usd_value = mul_div(lamports, aum, lp_mint_supply, Rounding::Down).map_err(ErrorCode::from)?;
+	let redemption_amount = get_token_amount(available_asset, redemption_usd_value, precise_price, exponent)?;
+	verbose_msg!("redemption_amount {}", redemption_amount);
+	let total_fee_in_basis_points = calculate_fee_basis_points(
+		aum,
+		available_asset,
+		exchange.total_weights,
+		precise_price,
+		exponent,
+		&ReserveChange {
+			amount: redemption_amount,
+			increment: false,
+			fee_basis_points: exchange.mint_burn_basis_points,
+			tax_basis_points: exchange.tax_basis_points,
+		}
+	)?;
+	verbose_msg!("total_fee_in_basis_points {}", total_fee_in_basis_points);
+	verbose_msg!("precise_price {}", precise_price);
+	// the share of the aum burned, grossed up by the fee that is kept from it, the products are taken in u128
+	let usd_value_of_burn = mul_div_basis_points(lamports, aum, lp_mint_supply, total_fee_in_basis_points, Rounding::Down)
+		.map_err(ErrorCode::from)?;
+
+	verbose_msg!("usd_value_of_burn {}", usd_value_of_burn);
+	
+	let burn_value_to_reserve_amount = get_token_amount(available_asset, usd_value_of_burn, precise_price, exponent)?;
+	
+	verbose_msg!("burn_value_to_reserve_amount {}", burn_value_to_reserve_amount);
+
+	let transfer_reserve_amount = mul_div(burn_value_to_reserve_amount, BASIS_POINTS_PRECISION, total_fee_in_basis_points, Rounding::Down)
+		.map_err(ErrorCode::from)?;
+	verbose_msg!("transfer_reserve_amount {}", transfer_reserve_amount);
+
+	available_asset.pool_reserves = available_asset.pool_reserves
+		.checked_sub(burn_value_to_reserve_amount)
+		.ok_or(ErrorCode::InsufficientReserves)?;
+	// the reserves held for open positions can not be withdrawn
+	require!(available_asset.occupied_reserves <= available_asset.pool_reserves, ErrorCode::InsufficientReserves);
+	available_asset.fee_reserves = available_asset.fee_reserves
+		.checked_add(burn_value_to_reserve_amount - transfer_reserve_amount)
+		.ok_or(ErrorCode::MathOverflow)?;
+	decrease_usd_debt(available_asset, burn_value_to_reserve_amount, precise_price, exponent)?;
+	verbose_msg!("pool reserves {} fee reserves {} usd debt {}", available_asset.pool_reserves, available_asset.fee_reserves, available_asset.usd_debt);
+
+	Ok(BurnLpQuote {
+		amount_out: transfer_reserve_amount,
+		fee_basis_points: total_fee_in_basis_points - BASIS_POINTS_PRECISION,
+		price: precise_price,
+		exponent,
+		aum,
+		pool_reserves: available_asset.pool_reserves,
+		fee_reserves: available_asset.fee_reserves,
+	})
+}
+
 impl<'info> BurnLpToken<'info> {
 	pub fn into_transfer_context<'a, 'b, 'c>(
 		&self, 
@@ -0,0 +1,140 @@
+use anchor_spl::token::{Token, TokenAccount};
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::*;
+use crate::error::ErrorCode;
+use decrease_position::apply_decrease;
+
+#[derive(Accounts)]
+#[instruction(
+	exchange_name: String,
+	asset_name: String,
+	collateral_asset_name: String,
+	is_long: bool,
+	receiver: Pubkey
+)]
+pub struct ClosePosition<'info> {
+	// user accounts
+	#[account(mut)]
+	pub user: Signer<'info>,
+	#[account(
+		mut,
+		constraint = receiver_token.owner == receiver,
+		constraint = receiver_token.mint == collateral_available_asset.mint_address,
+	)]
+	pub receiver_token: Box<Account<'info, TokenAccount>>,
+	// exchange Accounts
+	#[account(
+			mut,
+			seeds = [exchange_name.as_bytes(), user.key().as_ref(), available_asset.key().as_ref(), &[is_long as u8]],
+			bump,
+			close = user,
+	)]
+	pub position: Box<Account<'info, Position>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+	/// CHECK: this is our authority, no checked account required
+	#[account(
+			mut,
+			seeds = [EXCHANGE_AUTHORITY_SEED.as_bytes(), exchange_name.as_bytes()],
+			bump,
+	)]
+	pub exchange_authority: UncheckedAccount<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
+		bump
+	)]
+	pub available_asset: Account<'info, AvailableAsset>,
+	#[account(
+		seeds = [asset_name.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange_reserve_token: Box<Account<'info, TokenAccount>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), collateral_asset_name.as_bytes()],
+		bump
+	)]
+	pub collateral_available_asset: Account<'info, AvailableAsset>,
+	#[account(
+		mut,
+		seeds = [collateral_asset_name.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub collateral_exchange_reserve_token: Box<Account<'info, TokenAccount>>,
+	// Programs and Sysvars
+	pub system_program: Program<'info, System>,
+	pub token_program: Program<'info, Token>,
+	pub rent: Sysvar<'info, Rent>,
+}
+
+// decrease the whole size of the position, paying out the remaining collateral and profits
+// transfer them to the receiver
+// close the position account and return the rent to the user
+pub fn handler(
+	ctx: Context<ClosePosition>,
+	_exchange_name: String,
+	_asset_name: String,
+	_collateral_asset_name: String,
+	_is_long: bool,
+	_receiver: Pubkey
+) -> ProgramResult {
+	require!(
+		ctx.remaining_accounts.len() / 4 == ctx.accounts.exchange.assets.len(),
+		ErrorCode::MissingWhitelistedAssets
+	);
+	let size = ctx.accounts.position.size;
+
+	let tokens_out = apply_decrease(
+		&ctx.accounts.exchange,
+		&mut ctx.accounts.position,
+		&mut ctx.accounts.available_asset,
+		&mut ctx.accounts.collateral_available_asset,
+		ctx.remaining_accounts,
+		0,
+		size
+	)?;
+
+	let exchange_auth_bump = match ctx.bumps.get("exchange_authority") {
+			Some(bump) => {
+					bump
+			}
+			None => {
+					msg!("Wrong bump key. Available keys are {:?}", ctx.bumps.keys());
+					return Err(ErrorCode::InvalidBump.into());
+			}
+	};
+
+	let exchange_name = ctx.accounts.exchange.name.as_ref();
+	let seeds = exchange_authority_seeds!(
+			exchange_name = exchange_name,
+			bump = *exchange_auth_bump
+	);
+	let signer = &[&seeds[..]];
+
+	token::transfer(
+		ctx.accounts.into_transfer_context(signer),
+		tokens_out,
+	)?;
+	Ok(())
+}
+
+impl<'info> ClosePosition<'info> {
+	pub fn into_transfer_context<'a, 'b, 'c>(
+		&self,
+		signer: &'a [&'b [&'c [u8]]]
+	) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+			let cpi_accounts = Transfer {
+					from: self.collateral_exchange_reserve_token.to_account_info(),
+					to: self.receiver_token.to_account_info(),
+					authority: self.exchange_authority.to_account_info(),
+			};
+			let cpi_program = self.token_program.to_account_info();
+			CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+	}
+}
@@ -0,0 +1,464 @@
+use anchor_spl::token::{Token, TokenAccount};
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::*;
+use crate::error::ErrorCode;
+use crate::events::PositionDecreased;
+use mint_lp_token::{calculate_min_max_aum, get_token_decimals};
+use increase_position::{
+	normalize_price,
+	get_margin_fee,
+	validate_leverage,
+	validate_collateral,
+	sync_collateral_available_asset,
+	tokens_to_usd,
+	usd_to_tokens,
+	usd_to_tokens_round_up
+};
+use update_funding_rate::{update_cumulative_funding_rate, get_funding_fee};
+use jmx_math::{mul_div, Price, Rounding};
+
+#[derive(Accounts)]
+#[instruction(
+	exchange_name: String,
+	asset_name: String,
+	collateral_asset_name: String,
+	is_long: bool,
+	params: DecreasePositionParams
+)]
+pub struct DecreasePosition<'info> {
+	// user accounts
+	#[account(mut)]
+	pub user: Signer<'info>,
+	#[account(
+		mut,
+		constraint = receiver_token.owner == params.receiver,
+		constraint = receiver_token.mint == collateral_available_asset.mint_address,
+	)]
+	pub receiver_token: Box<Account<'info, TokenAccount>>,
+	// exchange Accounts
+	#[account(
+			mut,
+			seeds = [exchange_name.as_bytes(), user.key().as_ref(), available_asset.key().as_ref(), &[is_long as u8]],
+			bump,
+	)]
+	pub position: Box<Account<'info, Position>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+	/// CHECK: this is our authority, no checked account required
+	#[account(
+			mut,
+			seeds = [EXCHANGE_AUTHORITY_SEED.as_bytes(), exchange_name.as_bytes()],
+			bump,
+	)]
+	pub exchange_authority: UncheckedAccount<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
+		bump
+	)]
+	pub available_asset: Account<'info, AvailableAsset>,
+	#[account(
+		seeds = [asset_name.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange_reserve_token: Box<Account<'info, TokenAccount>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), collateral_asset_name.as_bytes()],
+		bump
+	)]
+	pub collateral_available_asset: Account<'info, AvailableAsset>,
+	#[account(
+		mut,
+		seeds = [collateral_asset_name.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub collateral_exchange_reserve_token: Box<Account<'info, TokenAccount>>,
+	// Programs and Sysvars
+	pub system_program: Program<'info, System>,
+	pub token_program: Program<'info, Token>,
+	pub rent: Sysvar<'info, Rent>,
+}
+
+// update the funding rate for the collateral asset
+// get the price of the asset and the collateral
+// calculate the pnl of the position and zero out profits that are too small to be taken before min_profit_time
+// realize the pnl on the portion of the position being closed
+// pay out profits from the pool and losses from the collateral
+// take the margin and funding fees
+// release the reserves held for the closed portion of the position
+// update the global short size and average price for shorts
+// transfer the collateral and profits to the receiver
+// decreasing the whole size goes through close_position, which also closes the position account
+/// Amounts to decrease a position by, the collateral delta is USD normalized with PRICE_DECIMALS
+/// and the size delta is in the smallest unit of the available asset's Mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct DecreasePositionParams {
+	pub collateral_delta: u64,
+	pub size_delta: u64,
+	/// owner of the token account receiving the collateral and profits
+	pub receiver: Pubkey,
+}
+
+pub fn handler(
+	ctx: Context<DecreasePosition>,
+	_exchange_name: String,
+	_asset_name: String,
+	_collateral_asset_name: String,
+	_is_long: bool,
+	params: DecreasePositionParams) -> ProgramResult {
+	require!(
+		ctx.remaining_accounts.len() / 4 == ctx.accounts.exchange.assets.len(),
+		ErrorCode::MissingWhitelistedAssets
+	);
+	require!(params.size_delta < ctx.accounts.position.size, ErrorCode::InvalidPositionDelta);
+	validate_decrease_not_paused(&ctx.accounts.available_asset, params.size_delta, ctx.accounts.position.size)?;
+
+	let tokens_out = apply_decrease(
+		&ctx.accounts.exchange,
+		&mut ctx.accounts.position,
+		&mut ctx.accounts.available_asset,
+		&mut ctx.accounts.collateral_available_asset,
+		ctx.remaining_accounts,
+		params.collateral_delta,
+		params.size_delta
+	)?;
+
+	let exchange_auth_bump = match ctx.bumps.get("exchange_authority") {
+			Some(bump) => {
+					bump
+			}
+			None => {
+					msg!("Wrong bump key. Available keys are {:?}", ctx.bumps.keys());
+					return Err(ErrorCode::InvalidBump.into());
+			}
+	};
+
+	let exchange_name = ctx.accounts.exchange.name.as_ref();
+	let seeds = exchange_authority_seeds!(
+			exchange_name = exchange_name,
+			bump = *exchange_auth_bump
+	);
+	let signer = &[&seeds[..]];
+
+	token::transfer(
+		ctx.accounts.into_transfer_context(signer),
+		tokens_out,
+	)?;
+	Ok(())
+}
+
+/// Price of a whitelisted mint normalized with PRICE_DECIMALS
+pub fn get_normalized_price(exchange: &Account<Exchange>, remaining_accounts: &[AccountInfo], mint: Pubkey) -> Result<u64> {
+	let (_, _, oracle_price) = calculate_min_max_aum(exchange, remaining_accounts, Some(mint))?;
+	let oracle_price = oracle_price.ok_or(ErrorCode::AssetNotWhitelisted)?;
+	let price = normalize_price(oracle_price.price, oracle_price.exponent)?;
+	require!(price > 0, ErrorCode::InvalidOracle);
+	Ok(price)
+}
+
+/// Decreases a position by size_delta, realizing its pnl, taking the margin and funding fees and
+/// releasing its reserves, returns the amount of the collateral token owed to the receiver
+pub fn apply_decrease<'info>(
+	exchange: &Account<'info, Exchange>,
+	position: &mut Account<'info, Position>,
+	available_asset: &mut Account<'info, AvailableAsset>,
+	collateral_available_asset: &mut Account<'info, AvailableAsset>,
+	remaining_accounts: &[AccountInfo],
+	collateral_delta: u64,
+	size_delta: u64
+) -> Result<u64> {
+	let is_long = position.is_long;
+	validate_collateral(is_long, available_asset, collateral_available_asset)?;
+	require!(position.collateral_mint == collateral_available_asset.mint_address, ErrorCode::InvalidCollateral);
+	let now = Clock::get()?.unix_timestamp;
+	update_cumulative_funding_rate(collateral_available_asset, now)?;
+	require!(position.size > 0, ErrorCode::InvalidPositionDelta);
+	require!(size_delta <= position.size, ErrorCode::InvalidPositionDelta);
+
+	let price = get_normalized_price(exchange, remaining_accounts, available_asset.mint_address)?;
+	let collateral_price = get_normalized_price(exchange, remaining_accounts, collateral_available_asset.mint_address)?;
+	let token_decimals = get_token_decimals(available_asset)?;
+	let collateral_decimals = get_token_decimals(collateral_available_asset)?;
+
+	let (has_profit, delta) = get_delta(
+		is_long,
+		position.size,
+		position.average_price,
+		price,
+		token_decimals,
+		&MinProfit {
+			last_increased_time: position.last_increased_time,
+			now,
+			min_profit_time: exchange.min_profit_time,
+			min_profit_basis_points: available_asset.min_profit_basis_points,
+		}
+	)?;
+	// only the pnl of the portion of the position being closed is realized
+	let adjusted_delta = mul_div(delta, size_delta, position.size, Rounding::Down).map_err(ErrorCode::from)?;
+	verbose_msg!("has_profit {} delta {} adjusted_delta {}", has_profit, delta, adjusted_delta);
+
+	let cumulative_funding_rate = collateral_available_asset.cumulative_funding_rate;
+	let fee_usd = get_margin_fee(size_delta, price, token_decimals, exchange.margin_fee_basis_points)?
+		.checked_add(get_funding_fee(position.size, price, token_decimals, position.entry_funding_rate, cumulative_funding_rate)?)
+		.ok_or(ErrorCode::MathOverflow)?;
+	let position_average_price = position.average_price;
+	position.entry_funding_rate = cumulative_funding_rate;
+	let (realized_pnl, in_profit) = add_signed(position.realized_pnl, position.in_profit, adjusted_delta, has_profit)?;
+	position.realized_pnl = realized_pnl;
+	position.in_profit = in_profit;
+
+	let collateral_before = position.collateral;
+	let mut usd_out = 0;
+	if has_profit {
+		usd_out = adjusted_delta;
+	} else {
+		position.collateral = position.collateral
+			.checked_sub(adjusted_delta)
+			.ok_or(ErrorCode::PositionMustBeLiquidated)?;
+	}
+
+	if size_delta == position.size {
+		usd_out = usd_out.checked_add(position.collateral).ok_or(ErrorCode::MathOverflow)?;
+		position.collateral = 0;
+	} else {
+		require!(collateral_delta <= position.collateral, ErrorCode::InvalidPositionDelta);
+		usd_out = usd_out.checked_add(collateral_delta).ok_or(ErrorCode::MathOverflow)?;
+		position.collateral -= collateral_delta;
+	}
+
+	if usd_out > fee_usd {
+		usd_out -= fee_usd;
+	} else {
+		position.collateral = position.collateral
+			.checked_sub(fee_usd)
+			.ok_or(ErrorCode::PositionMustBeLiquidated)?;
+	}
+
+	let collateral_removed = collateral_before - position.collateral;
+	let size_delta_usd = tokens_to_usd(size_delta, position.average_price, token_decimals)?;
+
+	let reserve_delta = mul_div(position.reserve_amount, size_delta, position.size, Rounding::Down).map_err(ErrorCode::from)?;
+	position.reserve_amount -= reserve_delta;
+	position.size -= size_delta;
+	validate_leverage(position.size, price, token_decimals, position.collateral)?;
+
+	let tokens_out = usd_to_tokens(usd_out, collateral_price, collateral_decimals)?;
+	let fee_tokens = usd_to_tokens_round_up(fee_usd, collateral_price, collateral_decimals)?;
+	collateral_available_asset.occupied_reserves = collateral_available_asset.occupied_reserves
+		.checked_sub(reserve_delta)
+		.ok_or(ErrorCode::MathOverflow)?;
+	collateral_available_asset.fee_reserves = collateral_available_asset.fee_reserves
+		.checked_add(fee_tokens)
+		.ok_or(ErrorCode::MathOverflow)?;
+	if is_long {
+		// long collateral is part of the pool, so everything paid out comes from the pool
+		collateral_available_asset.pool_reserves = collateral_available_asset.pool_reserves
+			.checked_sub(tokens_out)
+			.ok_or(ErrorCode::MathOverflow)?
+			.checked_sub(fee_tokens)
+			.ok_or(ErrorCode::InsufficientReserves)?;
+		// the collateral removed is now guaranteed to the long, while the closed size no longer is
+		collateral_available_asset.net_protocol_liabilities = collateral_available_asset.net_protocol_liabilities
+			.checked_add(collateral_removed)
+			.ok_or(ErrorCode::MathOverflow)?
+			.saturating_sub(size_delta_usd);
+	} else if has_profit {
+		// short collateral is held aside, so the pool only pays out the profits
+		collateral_available_asset.pool_reserves = collateral_available_asset.pool_reserves
+			.checked_sub(usd_to_tokens(adjusted_delta, collateral_price, collateral_decimals)?)
+			.ok_or(ErrorCode::InsufficientReserves)?;
+	} else {
+		// and takes in the losses
+		collateral_available_asset.pool_reserves = collateral_available_asset.pool_reserves
+			.checked_add(usd_to_tokens(adjusted_delta, collateral_price, collateral_decimals)?)
+			.ok_or(ErrorCode::MathOverflow)?;
+	}
+	require!(
+		collateral_available_asset.occupied_reserves <= collateral_available_asset.pool_reserves,
+		ErrorCode::InsufficientReserves
+	);
+	verbose_msg!("usd_out {} tokens_out {} fee_tokens {} pool reserves {}", usd_out, tokens_out, fee_tokens, collateral_available_asset.pool_reserves);
+
+	if !is_long {
+		available_asset.global_short_average_price = get_global_short_average_price_after_decrease(
+			available_asset.global_short_size,
+			available_asset.global_short_average_price,
+			size_delta,
+			position_average_price
+		)?;
+		available_asset.global_short_size = available_asset.global_short_size.checked_sub(size_delta).ok_or(ErrorCode::MathOverflow)?;
+		verbose_msg!("global short size {} global short average price {}", available_asset.global_short_size, available_asset.global_short_average_price);
+	}
+	sync_collateral_available_asset(available_asset, collateral_available_asset);
+
+	emit!(PositionDecreased {
+		owner: position.owner,
+		position: position.key(),
+		mint: available_asset.mint_address,
+		collateral_mint: collateral_available_asset.mint_address,
+		is_long,
+		collateral_delta,
+		size_delta,
+		normalized_price: price,
+		fee_usd,
+		has_profit,
+		realized_delta: adjusted_delta,
+		amount_out: tokens_out,
+		size: position.size,
+		collateral: position.collateral,
+		pool_reserves: collateral_available_asset.pool_reserves,
+		occupied_reserves: collateral_available_asset.occupied_reserves,
+	});
+	Ok(tokens_out)
+}
+
+/// A paused decrease still lets the position be closed in full, so only new risk is blocked
+pub fn validate_decrease_not_paused(available_asset: &AvailableAsset, size_delta: u64, size: u64) -> Result<()> {
+	require!(!available_asset.decrease_paused || size_delta == size, ErrorCode::ActionPaused);
+	Ok(())
+}
+
+/// Profits of a position increased less than min_profit_time before now are zeroed
+/// while they are below min_profit_basis_points of the position
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MinProfit {
+	pub last_increased_time: i64,
+	pub now: i64,
+	pub min_profit_time: u64,
+	pub min_profit_basis_points: u64,
+}
+
+/// Returns whether a position is in profit and the absolute USD pnl of the position, the size is in
+/// the smallest unit of a mint with token_decimals
+pub fn get_delta(
+	is_long: bool,
+	size: u64,
+	average_price: u64,
+	price: u64,
+	token_decimals: u8,
+	min_profit: &MinProfit
+) -> Result<(bool, u64)> {
+	let has_profit = if is_long { price > average_price } else { average_price > price };
+	let price_delta = price.abs_diff(average_price);
+	let delta = tokens_to_usd(size, price_delta, token_decimals)?;
+
+	let min_profit_end = min_profit.last_increased_time
+		.checked_add(min_profit.min_profit_time as i64)
+		.ok_or(ErrorCode::MathOverflow)?;
+	let min_bps = if min_profit.now > min_profit_end { 0 } else { min_profit.min_profit_basis_points };
+	let size_usd = tokens_to_usd(size, average_price, token_decimals)?;
+	if has_profit && (delta as u128) * (BASIS_POINTS_PRECISION as u128) <= (size_usd as u128) * (min_bps as u128) {
+		return Ok((has_profit, 0));
+	}
+	Ok((has_profit, delta))
+}
+
+/// Removes a position's size at its entry price from the global short average price,
+/// so the global average keeps matching the combined entry of the remaining shorts
+pub fn get_global_short_average_price_after_decrease(
+	global_short_size: u64,
+	global_short_average_price: u64,
+	size_delta: u64,
+	average_price: u64
+) -> Result<u64> {
+	let next_size = global_short_size.checked_sub(size_delta).ok_or(ErrorCode::MathOverflow)?;
+	if next_size == 0 {
+		return Ok(0);
+	}
+	Ok(Price(global_short_average_price)
+		.average_without(global_short_size, Price(average_price), size_delta)
+		.map_err(ErrorCode::from)?
+		.0)
+}
+
+/// Adds two sign and magnitude values together, returning the magnitude and whether it is positive
+pub fn add_signed(a: u64, a_positive: bool, b: u64, b_positive: bool) -> Result<(u64, bool)> {
+	if a_positive == b_positive {
+		return Ok((a.checked_add(b).ok_or(ErrorCode::MathOverflow)?, a_positive));
+	}
+	Ok(if a >= b { (a - b, a_positive) } else { (b - a, b_positive) })
+}
+
+impl<'info> DecreasePosition<'info> {
+	pub fn into_transfer_context<'a, 'b, 'c>(
+		&self,
+		signer: &'a [&'b [&'c [u8]]]
+	) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+			let cpi_accounts = Transfer {
+					from: self.collateral_exchange_reserve_token.to_account_info(),
+					to: self.receiver_token.to_account_info(),
+					authority: self.exchange_authority.to_account_info(),
+			};
+			let cpi_program = self.token_program.to_account_info();
+			CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const MIN_PROFIT: MinProfit = MinProfit { last_increased_time: 0, now: 100, min_profit_time: 15, min_profit_basis_points: 100 };
+
+	#[test]
+	fn paused_decrease_only_allows_closing() {
+		let asset = &mut AvailableAsset::default();
+		assert!(validate_decrease_not_paused(asset, 50, 100).is_ok());
+		asset.decrease_paused = true;
+		assert!(validate_decrease_not_paused(asset, 50, 100).is_err());
+		assert!(validate_decrease_not_paused(asset, 100, 100).is_ok());
+	}
+
+	#[test]
+	fn long_in_profit() {
+		assert_eq!(get_delta(true, 10, 100, 150, 0, &MIN_PROFIT).unwrap(), (true, 500));
+	}
+
+	#[test]
+	fn long_in_loss() {
+		assert_eq!(get_delta(true, 10, 100, 80, 0, &MIN_PROFIT).unwrap(), (false, 200));
+	}
+
+	#[test]
+	fn short_in_profit() {
+		assert_eq!(get_delta(false, 10, 100, 80, 0, &MIN_PROFIT).unwrap(), (true, 200));
+	}
+
+	#[test]
+	fn short_in_loss() {
+		assert_eq!(get_delta(false, 10, 100, 150, 0, &MIN_PROFIT).unwrap(), (false, 500));
+	}
+
+	#[test]
+	fn small_profit_before_min_profit_time_is_zeroed() {
+		assert_eq!(get_delta(true, 10, 10_000, 10_050, 0, &MinProfit { last_increased_time: 90, ..MIN_PROFIT }).unwrap(), (true, 0));
+		assert_eq!(get_delta(true, 10, 10_000, 10_050, 0, &MinProfit { last_increased_time: 80, ..MIN_PROFIT }).unwrap(), (true, 500));
+		assert_eq!(get_delta(true, 10, 10_000, 10_200, 0, &MinProfit { last_increased_time: 90, ..MIN_PROFIT }).unwrap(), (true, 2_000));
+	}
+
+	#[test]
+	fn delta_is_valued_with_the_decimals_of_the_asset() {
+		// 2 SOL with 9 decimals bought at $25 are up $10 at $30
+		assert_eq!(get_delta(true, 2_000_000_000, 25_000_000, 30_000_000, 9, &MIN_PROFIT).unwrap(), (true, 10_000_000));
+	}
+
+	#[test]
+	fn global_short_average_price_keeps_remaining_entries() {
+		// shorts of 100 @ 20 and 300 @ 40 average to 35
+		assert_eq!(get_global_short_average_price_after_decrease(400, 35, 100, 20).unwrap(), 40);
+		assert_eq!(get_global_short_average_price_after_decrease(400, 35, 400, 35).unwrap(), 0);
+	}
+
+	#[test]
+	fn adds_signed_values() {
+		assert_eq!(add_signed(5, true, 3, true).unwrap(), (8, true));
+		assert_eq!(add_signed(5, true, 8, false).unwrap(), (3, false));
+		assert_eq!(add_signed(5, false, 3, true).unwrap(), (2, false));
+	}
+}
@@ -0,0 +1,87 @@
+use anchor_spl::token::Mint;
+use anchor_lang::prelude::*;
+use solana_program::program::set_return_data;
+use crate::constants::*;
+use crate::*;
+use crate::error::ErrorCode;
+use mint_lp_token::calculate_min_max_aum;
+use jmx_math::{mul_div, Rounding};
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String)]
+pub struct GetLpPrice<'info> {
+	#[account(
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+	#[account(
+		seeds = [LP_MINT_SEED.as_bytes(), exchange_name.as_bytes()],
+		bump
+	)]
+	pub lp_mint: Box<Account<'info, Mint>>,
+}
+
+/// AUM of the pool and the USD price of one whole LP token, both normalized with PRICE_DECIMALS.
+/// Min values use the min price of every asset and max values the max price, burns are priced
+/// at the min and mints at the max
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct LpPrice {
+	pub min_aum: u64,
+	pub max_aum: u64,
+	pub lp_supply: u64,
+	pub min_price: u64,
+	pub max_price: u64,
+}
+
+// returns the LpPrice of the pool as borsh return data, remaining_accounts are the
+// same groups of 4 accounts per whitelisted asset that mint_lp_token takes
+pub fn handler(ctx: Context<GetLpPrice>, _exchange_name: String) -> ProgramResult {
+	let lp_price = get_lp_price(&ctx.accounts.exchange, ctx.remaining_accounts, ctx.accounts.lp_mint.supply)?;
+	verbose_msg!("lp price {:?}", lp_price);
+	set_return_data(&lp_price.try_to_vec()?);
+	Ok(())
+}
+
+pub fn get_lp_price(exchange: &Account<Exchange>, remaining_accounts: &[AccountInfo], lp_supply: u64) -> Result<LpPrice> {
+	let (min_aum, max_aum, _) = calculate_min_max_aum(exchange, remaining_accounts, None)?;
+	calculate_lp_price(min_aum, max_aum, lp_supply)
+}
+
+/// Divides the aum over the lp supply, an empty pool mints its first LP token at 1 USD
+pub fn calculate_lp_price(min_aum: u64, max_aum: u64, lp_supply: u64) -> Result<LpPrice> {
+	let price_per_lp_token = |aum: u64| -> Result<u64> {
+		if lp_supply == 0 {
+			return Ok(PRICE_DECIMALS_FACTOR);
+		}
+		Ok(mul_div(aum, 10_u64.pow(LP_DECIMALS as u32), lp_supply, Rounding::Down).map_err(ErrorCode::from)?)
+	};
+	Ok(LpPrice {
+		min_aum,
+		max_aum,
+		lp_supply,
+		min_price: price_per_lp_token(min_aum)?,
+		max_price: price_per_lp_token(max_aum)?,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn empty_pool_prices_lp_at_one_usd() {
+		let lp_price = calculate_lp_price(0, 0, 0).unwrap();
+		assert_eq!(lp_price.min_price, PRICE_DECIMALS_FACTOR);
+		assert_eq!(lp_price.max_price, PRICE_DECIMALS_FACTOR);
+	}
+
+	#[test]
+	fn lp_price_is_aum_per_whole_lp_token() {
+		// $150 and $151 of aum over 100 LP tokens
+		let lp_price = calculate_lp_price(150 * PRICE_DECIMALS_FACTOR, 151 * PRICE_DECIMALS_FACTOR, 100 * 10_u64.pow(LP_DECIMALS as u32)).unwrap();
+		assert_eq!(lp_price.min_price, 1_500_000);
+		assert_eq!(lp_price.max_price, 1_510_000);
+		assert_eq!(lp_price.lp_supply, 10_000_000_000);
+	}
+}
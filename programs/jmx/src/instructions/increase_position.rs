@@ -1,19 +1,25 @@
-use std::ops::RangeBounds;
-
-use anchor_spl::token::{Mint, Token};
+use anchor_spl::token::{Token, TokenAccount};
 use anchor_lang::prelude::*;
 use crate::constants::*;
 use crate::*;
+use crate::error::ErrorCode;
+use crate::events::PositionIncreased;
+use mint_lp_token::{calculate_aum, get_token_decimals};
+use update_funding_rate::{update_cumulative_funding_rate, get_funding_fee};
+use jmx_math::{apply_basis_points, Price, Rounding, TokenAmount, Usd};
 
 #[derive(Accounts)]
-#[instruction(exchange_name: String, asset_name: String)]
+#[instruction(exchange_name: String, asset_name: String, collateral_asset_name: String, is_long: bool)]
 pub struct IncreasePosition<'info> {
 	// exchange Authority accounts
 	#[account(mut)]
 	pub user: Signer<'info>,
+	#[account(mut)]
+	pub user_collateral_token: Box<Account<'info, TokenAccount>>,
 	// exchange Accounts
 	#[account(
-			seeds = [exchange_name.as_bytes(), user.key().as_ref(), available_asset.key().as_ref()],
+			mut,
+			seeds = [exchange_name.as_bytes(), user.key().as_ref(), available_asset.key().as_ref(), &[is_long as u8]],
 			bump,
 	)]
 	pub position: Box<Account<'info, Position>>,
@@ -31,30 +37,338 @@ pub struct IncreasePosition<'info> {
 	)]
 	pub exchange_authority: UncheckedAccount<'info>,
 	#[account(
+		mut,
 		seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
 		bump
 	)]
 	pub available_asset: Account<'info, AvailableAsset>,
-	#[account()]
-	pub collateral_mint: Box<Account<'info, Mint>>,
+	#[account(
+		seeds = [asset_name.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange_reserve_token: Box<Account<'info, TokenAccount>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), collateral_asset_name.as_bytes()],
+		bump
+	)]
+	pub collateral_available_asset: Account<'info, AvailableAsset>,
+	#[account(
+		mut,
+		seeds = [collateral_asset_name.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub collateral_exchange_reserve_token: Box<Account<'info, TokenAccount>>,
 	// Programs and Sysvars
 	pub system_program: Program<'info, System>,
 	pub token_program: Program<'info, Token>,
 	pub rent: Sysvar<'info, Rent>,
 }
 
-// Validate the position is healthy by checking the liquidation status
 // Validate the tokens in collateral and the available asset are correct
-// get the price of the asset with bps attatched/subtracted
-// update the funding rate for the specific token
+// update the funding rate for the collateral asset
+// transfer the collateral into the exchange reserves
+// get the price of the asset and the collateral
 // if new position
 	// set average price to price
-// else set it to the composite of the new and old price
-// get the margin fees
-pub fn handler(ctx: Context<IncreasePosition>, exchange_name: String, asset_name: String) -> ProgramResult {
-	assert!(
+// else set it to the size weighted composite of the new and old price
+// take the margin and funding fees out of the collateral
+// reserve the size of the position from the collateral pool
+// update the global short size and average price for shorts
+// Validate the position is healthy by checking the leverage
+pub fn handler(
+	ctx: Context<IncreasePosition>,
+	_exchange_name: String,
+	_asset_name: String,
+	_collateral_asset_name: String,
+	is_long: bool,
+	collateral_amount: u64,
+	size_delta: u64
+) -> ProgramResult {
+	require!(
 		ctx.accounts.exchange.assets.contains(&ctx.accounts.available_asset.mint_address),
-		"invalid available asset provided"
+		ErrorCode::AssetNotWhitelisted
+	);
+	require!(!ctx.accounts.exchange.paused, ErrorCode::ExchangePaused);
+	require!(!ctx.accounts.available_asset.increase_paused, ErrorCode::ActionPaused);
+	require!(
+		!ctx.accounts.available_asset.withdraw_only && !ctx.accounts.collateral_available_asset.withdraw_only,
+		ErrorCode::AssetWithdrawOnly
 	);
+	require!(
+		ctx.remaining_accounts.len() / 4 == ctx.accounts.exchange.assets.len(),
+		ErrorCode::MissingWhitelistedAssets
+	);
+	validate_collateral(
+		is_long,
+		&ctx.accounts.available_asset,
+		&ctx.accounts.collateral_available_asset
+	)?;
+	require!(
+		ctx.accounts.position.collateral_mint == ctx.accounts.collateral_available_asset.mint_address,
+		ErrorCode::InvalidCollateral
+	);
+	require!(size_delta > 0 || collateral_amount > 0, ErrorCode::InvalidPositionDelta);
+
+	let (_, _, oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
+		ctx.remaining_accounts,
+		&ctx.accounts.exchange_reserve_token
+	)?;
+	let price = normalize_price(oracle_price.price, oracle_price.exponent)?;
+	let (_, _, collateral_oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
+		ctx.remaining_accounts,
+		&ctx.accounts.collateral_exchange_reserve_token
+	)?;
+	let collateral_price = normalize_price(collateral_oracle_price.price, collateral_oracle_price.exponent)?;
+	require!(price > 0 && collateral_price > 0, ErrorCode::InvalidOracle);
+	verbose_msg!("normalized price {} collateral price {}", price, collateral_price);
+
+	token::transfer(
+		ctx.accounts.into_transfer_context(),
+		collateral_amount,
+	)?;
+
+	// sizes are in the smallest unit of the asset and collateral in the smallest unit of the collateral
+	// mint, both are valued in USD normalized with PRICE_DECIMALS with the decimals of their mint
+	let token_decimals = get_token_decimals(&ctx.accounts.available_asset)?;
+	let collateral_decimals = get_token_decimals(&ctx.accounts.collateral_available_asset)?;
+	let margin_fee_basis_points = ctx.accounts.exchange.margin_fee_basis_points;
+	let position = &mut ctx.accounts.position;
+	let collateral_asset = &mut ctx.accounts.collateral_available_asset;
+	let now = Clock::get()?.unix_timestamp;
+	update_cumulative_funding_rate(collateral_asset, now)?;
+	// funding is owed on the existing size up until this increase
+	let funding_fee_usd = get_funding_fee(
+		position.size,
+		price,
+		token_decimals,
+		position.entry_funding_rate,
+		collateral_asset.cumulative_funding_rate
+	)?;
+
+	position.average_price = get_next_average_price(
+		position.size,
+		position.average_price,
+		size_delta,
+		price
+	)?;
+
+	let size_delta_usd = tokens_to_usd(size_delta, price, token_decimals)?;
+	let fee_usd = get_margin_fee(size_delta, price, token_decimals, margin_fee_basis_points)?
+		.checked_add(funding_fee_usd)
+		.ok_or(ErrorCode::MathOverflow)?;
+	let collateral_delta_usd = tokens_to_usd(collateral_amount, collateral_price, collateral_decimals)?;
+	verbose_msg!("collateral_delta_usd {} fee_usd {}", collateral_delta_usd, fee_usd);
+	position.collateral = position.collateral
+		.checked_add(collateral_delta_usd)
+		.ok_or(ErrorCode::MathOverflow)?
+		.checked_sub(fee_usd)
+		.ok_or(ErrorCode::MathOverflow)?;
+
+	// fees are paid out of the deposited collateral, rounded up in favor of the pool
+	let fee_tokens = usd_to_tokens_round_up(fee_usd, collateral_price, collateral_decimals)?;
+	collateral_asset.fee_reserves = collateral_asset.fee_reserves.checked_add(fee_tokens).ok_or(ErrorCode::MathOverflow)?;
+	// long collateral is the asset itself and becomes part of the pool, short collateral is held aside
+	// the pool owes longs their size less their collateral, tracked in USD as the guaranteed usd
+	if is_long {
+		collateral_asset.pool_reserves = collateral_asset.pool_reserves
+			.checked_add(collateral_amount)
+			.ok_or(ErrorCode::MathOverflow)?
+			.checked_sub(fee_tokens)
+			.ok_or(ErrorCode::MathOverflow)?;
+		collateral_asset.net_protocol_liabilities = collateral_asset.net_protocol_liabilities
+			.checked_add(size_delta_usd)
+			.ok_or(ErrorCode::MathOverflow)?
+			.checked_add(fee_usd)
+			.ok_or(ErrorCode::MathOverflow)?
+			.saturating_sub(collateral_delta_usd);
+	}
+
+	// longs reserve the asset itself, shorts reserve the collateral to pay out profits
+	let reserve_delta = if is_long {
+		size_delta
+	} else {
+		usd_to_tokens_round_up(size_delta_usd, collateral_price, collateral_decimals)?
+	};
+	position.size = position.size.checked_add(size_delta).ok_or(ErrorCode::MathOverflow)?;
+	position.reserve_amount = position.reserve_amount.checked_add(reserve_delta).ok_or(ErrorCode::MathOverflow)?;
+	collateral_asset.occupied_reserves = collateral_asset.occupied_reserves.checked_add(reserve_delta).ok_or(ErrorCode::MathOverflow)?;
+	require!(collateral_asset.occupied_reserves <= collateral_asset.pool_reserves, ErrorCode::InsufficientReserves);
+
+	position.entry_funding_rate = collateral_asset.cumulative_funding_rate;
+	position.last_increased_time = now;
+
+	validate_leverage(position.size, price, token_decimals, position.collateral)?;
+	verbose_msg!(
+		"position size {} collateral {} average price {} occupied reserves {}",
+		position.size, position.collateral, position.average_price, collateral_asset.occupied_reserves
+	);
+
+	if !is_long {
+		let asset = &mut ctx.accounts.available_asset;
+		asset.global_short_average_price = get_next_average_price(
+			asset.global_short_size,
+			asset.global_short_average_price,
+			size_delta,
+			price
+		)?;
+		asset.global_short_size = asset.global_short_size.checked_add(size_delta).ok_or(ErrorCode::MathOverflow)?;
+		verbose_msg!("global short size {} global short average price {}", asset.global_short_size, asset.global_short_average_price);
+	}
+	sync_collateral_available_asset(&mut ctx.accounts.available_asset, &ctx.accounts.collateral_available_asset);
+	let position = &ctx.accounts.position;
+	let collateral_asset = &ctx.accounts.collateral_available_asset;
+	emit!(PositionIncreased {
+		owner: position.owner,
+		position: position.key(),
+		mint: ctx.accounts.available_asset.mint_address,
+		collateral_mint: collateral_asset.mint_address,
+		is_long,
+		collateral_amount,
+		size_delta,
+		normalized_price: price,
+		fee_usd,
+		size: position.size,
+		collateral: position.collateral,
+		average_price: position.average_price,
+		reserve_amount: position.reserve_amount,
+		pool_reserves: collateral_asset.pool_reserves,
+		occupied_reserves: collateral_asset.occupied_reserves,
+	});
 	Ok(())
-}
\ No newline at end of file
+}
+
+/// Longs are collateralized with the asset itself, shorts with a stable asset
+pub fn validate_collateral(is_long: bool, available_asset: &Account<AvailableAsset>, collateral_available_asset: &Account<AvailableAsset>) -> Result<()> {
+	if is_long {
+		require!(available_asset.key() == collateral_available_asset.key(), ErrorCode::InvalidCollateral);
+	} else {
+		require!(available_asset.shortable_token, ErrorCode::AssetNotShortable);
+		require!(collateral_available_asset.stable_token, ErrorCode::InvalidCollateral);
+	}
+	Ok(())
+}
+
+/// Longs pass the available asset as the collateral asset as well. Both accounts are written back
+/// when the instruction exits, so they must hold the same data or the last write wins
+pub fn sync_collateral_available_asset<'info>(
+	available_asset: &mut Account<'info, AvailableAsset>,
+	collateral_available_asset: &Account<'info, AvailableAsset>
+) {
+	if available_asset.key() == collateral_available_asset.key() {
+		available_asset.set_inner((**collateral_available_asset).clone());
+	}
+}
+
+/// USD value normalized with PRICE_DECIMALS of an amount of a token with token_decimals at a normalized price
+pub fn tokens_to_usd(amount: u64, price: u64, token_decimals: u8) -> Result<u64> {
+	Ok(TokenAmount(amount).to_usd(Price(price), token_decimals, Rounding::Down).map_err(ErrorCode::from)?.0)
+}
+
+/// Converts a USD value normalized with PRICE_DECIMALS into tokens with token_decimals, rounding down
+/// for amounts the pool pays out
+pub fn usd_to_tokens(usd: u64, price: u64, token_decimals: u8) -> Result<u64> {
+	Ok(Usd(usd).to_tokens(Price(price), token_decimals, Rounding::Down).map_err(ErrorCode::from)?.0)
+}
+
+/// Converts a USD value normalized with PRICE_DECIMALS into tokens with token_decimals, rounding up
+/// in favor of the pool
+pub fn usd_to_tokens_round_up(usd: u64, price: u64, token_decimals: u8) -> Result<u64> {
+	Ok(Usd(usd).to_tokens(Price(price), token_decimals, Rounding::Up).map_err(ErrorCode::from)?.0)
+}
+
+/// Converts a pyth price and exponent into a USD price normalized with PRICE_DECIMALS
+pub fn normalize_price(precise_price: u64, exponent: u64) -> Result<u64> {
+	Ok(Price::from_oracle(precise_price, exponent as u32).map_err(ErrorCode::from)?.0)
+}
+
+/// Size weighted average of the current entry price and the price of the new size
+pub fn get_next_average_price(size: u64, average_price: u64, size_delta: u64, price: u64) -> Result<u64> {
+	let next_size = size.checked_add(size_delta).ok_or(ErrorCode::MathOverflow)?;
+	if size == 0 || next_size == 0 {
+		return Ok(price);
+	}
+	Ok(Price(average_price).weighted_average(size, Price(price), size_delta).map_err(ErrorCode::from)?.0)
+}
+
+/// Fee charged on the USD value of a change in position size
+pub fn get_margin_fee(size_delta: u64, price: u64, token_decimals: u8, margin_fee_basis_points: u64) -> Result<u64> {
+	let size_delta_usd = tokens_to_usd(size_delta, price, token_decimals)?;
+	Ok(apply_basis_points(size_delta_usd, margin_fee_basis_points, Rounding::Down).map_err(ErrorCode::from)?)
+}
+
+pub fn validate_leverage(size: u64, price: u64, token_decimals: u8, collateral: u64) -> Result<()> {
+	if size == 0 {
+		return Ok(());
+	}
+	let size_usd = tokens_to_usd(size, price, token_decimals)?;
+	require!(collateral > 0, ErrorCode::PositionHasNoCollateral);
+	require!(size_usd >= collateral, ErrorCode::CollateralExceedsSize);
+	require!(
+		size_usd <= collateral.checked_mul(MAX_LEVERAGE).ok_or(ErrorCode::MathOverflow)?,
+		ErrorCode::MaxLeverageExceeded
+	);
+	Ok(())
+}
+
+impl<'info> IncreasePosition<'info> {
+	pub fn into_transfer_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+			let cpi_accounts = Transfer {
+					from: self.user_collateral_token.to_account_info(),
+					to: self.collateral_exchange_reserve_token.to_account_info(),
+					authority: self.user.to_account_info(),
+			};
+			let cpi_program = self.token_program.to_account_info();
+			CpiContext::new(cpi_program, cpi_accounts)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn average_price_of_new_position_is_price() {
+		assert_eq!(get_next_average_price(0, 0, 100, 25_000_000).unwrap(), 25_000_000);
+	}
+
+	#[test]
+	fn average_price_is_size_weighted() {
+		assert_eq!(get_next_average_price(100, 20_000_000, 300, 40_000_000).unwrap(), 35_000_000);
+	}
+
+	#[test]
+	fn normalizes_pyth_price() {
+		assert_eq!(normalize_price(1_000_000, 4).unwrap(), 100_000_000);
+		assert_eq!(normalize_price(1_000_000, 6).unwrap(), 1_000_000);
+	}
+
+	#[test]
+	fn margin_fee_in_usd() {
+		// 200 whole tokens at $100 with a 10bps fee
+		assert_eq!(get_margin_fee(200, 100_000_000, 0, 10).unwrap(), 20_000_000);
+		assert_eq!(get_margin_fee(200_000_000_000, 100_000_000, 9, 10).unwrap(), 20_000_000);
+	}
+
+	#[test]
+	fn usd_to_tokens_rounds_up() {
+		assert_eq!(usd_to_tokens_round_up(2_000_000, 100_000_000, 0).unwrap(), 1);
+		assert_eq!(usd_to_tokens_round_up(200_000_000, 100_000_000, 0).unwrap(), 2);
+		assert_eq!(usd_to_tokens(2_000_000, 100_000_000, 0).unwrap(), 0);
+	}
+
+	#[test]
+	fn position_usd_uses_the_decimals_of_each_mint() {
+		// a 2 SOL short at $25 with 9 decimals is collateralized with 10 USDC with 6 decimals
+		let size_usd = tokens_to_usd(2_000_000_000, 25_000_000, 9).unwrap();
+		let collateral_usd = tokens_to_usd(10_000_000, 1_000_000, 6).unwrap();
+		assert_eq!(size_usd, 50_000_000);
+		assert_eq!(collateral_usd, 10_000_000);
+		// the short reserves $50 of USDC
+		assert_eq!(usd_to_tokens_round_up(size_usd, 1_000_000, 6).unwrap(), 50_000_000);
+		assert!(validate_leverage(2_000_000_000, 25_000_000, 9, collateral_usd).is_ok());
+		assert!(validate_leverage(2_000_000_000, 25_000_000, 9, 999_999).is_err());
+	}
+}
@@ -1,7 +1,10 @@
 use anchor_spl::token::{Mint, Token, TokenAccount};
 use anchor_lang::prelude::*;
 use crate::*;
+use crate::error::ErrorCode;
+use crate::events::AssetListed;
 use crate::constants::EXCHANGE_AUTHORITY_SEED;
+use update_funding_rate::update_cumulative_funding_rate;
 
 #[derive(Accounts)]
 #[instruction(exchange_name: String, asset_name: String, available_asset: AvailableAsset)]
@@ -9,9 +12,9 @@ pub struct InitializeAvailableAsset<'info> {
     // exchange Authority accounts
     #[account(
 			mut,
-			constraint = exchange_admin.key() == exchange.admin
+			constraint = asset_manager.key() == exchange.asset_manager @ ErrorCode::UnauthorizedRole
 		)]
-    pub exchange_admin: Signer<'info>,
+    pub asset_manager: Signer<'info>,
     // exchange Accounts
     #[account(
 				mut,
@@ -30,7 +33,7 @@ pub struct InitializeAvailableAsset<'info> {
 			init,
 			seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
 			bump,
-			payer = exchange_admin,
+			payer = asset_manager,
 		)]
 		pub available_asset: Account<'info, AvailableAsset>,
 		#[account(
@@ -39,7 +42,7 @@ pub struct InitializeAvailableAsset<'info> {
 			token::authority = exchange_authority,
 			seeds = [asset_name.as_bytes(), exchange_name.as_bytes()],
 			bump,
-			payer = exchange_admin
+			payer = asset_manager
 		)]
 		pub exchange_reserve_token: Box<Account<'info, TokenAccount>>,
 		#[account(mut)]
@@ -54,29 +57,55 @@ pub struct InitializeAvailableAsset<'info> {
 // Should throw an error if someone tries to init an already initialized available asset account or an already init-ed token account for that asset
 pub fn handler(ctx: Context<InitializeAvailableAsset>, exchange_name: String, asset_name: String, asset_data: AvailableAsset) -> ProgramResult {
 	let asset = &mut ctx.accounts.available_asset;
-	msg!("ctx.accounts.mint.key() {:?} asset_data.mint_address {:?}", ctx.accounts.mint.key(), asset_data.mint_address );
-	assert!(ctx.accounts.mint.key() == asset_data.mint_address, "Mints are not same");
+	verbose_msg!("ctx.accounts.mint.key() {:?} asset_data.mint_address {:?}", ctx.accounts.mint.key(), asset_data.mint_address );
+	require!(ctx.accounts.mint.key() == asset_data.mint_address, ErrorCode::InvalidAvailableAsset);
+	// reserves are valued with the decimals of the mint, so they have to be the real ones
+	require!(asset_data.token_decimals == ctx.accounts.mint.decimals as u64, ErrorCode::InvalidTokenDecimals);
+	update_available_asset::validate_price_config(&asset_data)?;
 
 	asset.mint_address = ctx.accounts.mint.key();
+	asset.exchange = ctx.accounts.exchange.key();
 	asset.token_decimals = asset_data.token_decimals;
 	asset.min_profit_basis_points = asset_data.min_profit_basis_points;
 	asset.max_lptoken_amount = asset_data.max_lptoken_amount;
+	asset.max_usd_debt = asset_data.max_usd_debt;
 	asset.stable_token = asset_data.stable_token;
 	asset.shortable_token = asset_data.shortable_token;
+	asset.withdraw_only = false;
+	asset.mint_paused = false;
+	asset.burn_paused = false;
+	asset.swap_in_paused = false;
+	asset.swap_out_paused = false;
+	asset.increase_paused = false;
+	asset.decrease_paused = false;
 	asset.cumulative_funding_rate = 0;
-	// Need to set time to current time with clock
-	asset.last_funding_time = asset_data.last_funding_time;
+	asset.last_funding_time = 0;
+	update_cumulative_funding_rate(asset, Clock::get()?.unix_timestamp)?;
 	asset.oracle_address = asset_data.oracle_address;
 	asset.backup_oracle_address = asset_data.backup_oracle_address;
+	asset.max_price_age_slots = asset_data.max_price_age_slots;
+	asset.max_price_confidence_basis_points = asset_data.max_price_confidence_basis_points;
+	asset.spread_basis_points = asset_data.spread_basis_points;
 	asset.global_short_size = 0;
+	asset.global_short_average_price = 0;
 	asset.net_protocol_liabilities = 0; 
 	asset.token_weight = asset_data.token_weight;
 	asset.occupied_reserves = 0;
 	asset.fee_reserves = 0;
 	asset.pool_reserves = 0;
+	asset.usd_debt = 0;
 
 	let exchange = &mut ctx.accounts.exchange;
-	exchange.total_weights += asset.token_weight;
+	exchange.total_weights = exchange.total_weights.checked_add(asset.token_weight).ok_or(ErrorCode::MathOverflow)?;
+	emit!(AssetListed {
+		mint: asset.mint_address,
+		oracle: asset.oracle_address,
+		backup_oracle: asset.backup_oracle_address,
+		token_weight: asset.token_weight,
+		total_weights: exchange.total_weights,
+		stable_token: asset.stable_token,
+		shortable_token: asset.shortable_token,
+	});
 
 	Ok(())
 }
\ No newline at end of file
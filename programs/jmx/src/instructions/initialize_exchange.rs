@@ -27,7 +27,7 @@ pub struct InitializeExchange<'info> {
     pub exchange_authority: UncheckedAccount<'info>,
     #[account(
         init,
-        mint::decimals = 8 as u8,
+        mint::decimals = LP_DECIMALS,
         mint::authority = exchange_authority,
         seeds = [LP_MINT_SEED.as_bytes(), exchange_name.as_bytes()],
         bump,
@@ -55,7 +55,15 @@ pub fn handler(ctx: Context<InitializeExchange>, exchange_name: String) -> Progr
 	exchange.liquidation_fee_usd = 40;
 	exchange.min_profit_time = 15;
 	exchange.total_weights = 0;
+	exchange.treasury_fee_basis_points = BASIS_POINTS_PRECISION;
 	exchange.admin = ctx.accounts.exchange_admin.key();
+	exchange.pending_admin = Pubkey::default();
+	exchange.fee_manager = exchange.admin;
+	exchange.asset_manager = exchange.admin;
+	exchange.pauser = exchange.admin;
+	exchange.keeper = exchange.admin;
+	exchange.treasury = exchange.admin;
+	exchange.paused = false;
 	exchange.name = name_data;
 
 	Ok(())
@@ -64,5 +72,6 @@ pub fn handler(ctx: Context<InitializeExchange>, exchange_name: String) -> Progr
 impl Exchange {
 	const LEN: usize = 32 * 20 
 	+ (8 * SMALL_UINTS_IN_EXCHANGE as usize)
-	+ 32;
+	+ 32 * 7
+	+ 1;
 }
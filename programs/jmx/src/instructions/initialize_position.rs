@@ -2,9 +2,10 @@ use anchor_spl::token::{Mint, Token};
 use anchor_lang::prelude::*;
 use crate::constants::*;
 use crate::*;
+use crate::error::ErrorCode;
 
 #[derive(Accounts)]
-#[instruction(exchange_name: String, asset_name: String)]
+#[instruction(exchange_name: String, asset_name: String, is_long: bool)]
 pub struct InitializePosition<'info> {
     // exchange Authority accounts
     #[account(mut)]
@@ -12,7 +13,7 @@ pub struct InitializePosition<'info> {
     // exchange Accounts
     #[account(
 			init,
-			seeds = [exchange_name.as_bytes(), user.key().as_ref(), available_asset.key().as_ref()],
+			seeds = [exchange_name.as_bytes(), user.key().as_ref(), available_asset.key().as_ref(), &[is_long as u8]],
 			bump,
 			payer = user,
 			space = Position::LEN,
@@ -46,12 +47,18 @@ pub struct InitializePosition<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
-pub fn handler(ctx: Context<InitializePosition>, exchange_name: String, asset_name: String) -> ProgramResult {
+pub fn handler(ctx: Context<InitializePosition>, exchange_name: String, asset_name: String, is_long: bool) -> ProgramResult {
+	require!(
+		is_long || ctx.accounts.available_asset.shortable_token,
+		ErrorCode::AssetNotShortable
+	);
 	let position = &mut ctx.accounts.position;
 
 	position.owner = *ctx.accounts.user.key;
+	position.is_long = is_long;
 	position.collateral_mint = ctx.accounts.collateral_mint.key();
 	position.size = 0;
+	position.collateral = 0;
 	position.average_price = 0;
 	position.reserve_amount = 0;
 	position.entry_funding_rate = 0;
@@ -63,6 +70,6 @@ pub fn handler(ctx: Context<InitializePosition>, exchange_name: String, asset_na
 
 impl Position {
 	const LEN: usize = 32 * 3 
-	+ (6 * SMALL_UINTS_IN_EXCHANGE as usize)
+	+ (7 * SMALL_UINTS_IN_EXCHANGE as usize)
 	+ 4;
 }
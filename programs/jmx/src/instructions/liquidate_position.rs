@@ -0,0 +1,355 @@
+use anchor_spl::token::{Token, TokenAccount};
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::*;
+use crate::error::ErrorCode;
+use crate::events::Liquidated;
+use mint_lp_token::{calculate_aum, get_token_decimals};
+use increase_position::{
+	normalize_price,
+	get_margin_fee,
+	validate_collateral,
+	sync_collateral_available_asset,
+	tokens_to_usd,
+	usd_to_tokens,
+	usd_to_tokens_round_up
+};
+use decrease_position::{get_delta, get_global_short_average_price_after_decrease, MinProfit};
+use update_funding_rate::{update_cumulative_funding_rate, get_funding_fee};
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String, asset_name: String, collateral_asset_name: String, is_long: bool)]
+pub struct LiquidatePosition<'info> {
+	// liquidator accounts
+	#[account(mut)]
+	pub liquidator: Signer<'info>,
+	#[account(
+		mut,
+		constraint = liquidator_token.owner == liquidator.key(),
+		constraint = liquidator_token.mint == collateral_available_asset.mint_address,
+	)]
+	pub liquidator_token: Box<Account<'info, TokenAccount>>,
+	/// CHECK: owner of the position being liquidated, only receives the rent of the closed position
+	#[account(
+		mut,
+		constraint = position_owner.key() == position.owner,
+	)]
+	pub position_owner: UncheckedAccount<'info>,
+	// exchange Accounts
+	#[account(
+			mut,
+			seeds = [exchange_name.as_bytes(), position_owner.key().as_ref(), available_asset.key().as_ref(), &[is_long as u8]],
+			bump,
+			close = position_owner,
+	)]
+	pub position: Box<Account<'info, Position>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+	/// CHECK: this is our authority, no checked account required
+	#[account(
+			mut,
+			seeds = [EXCHANGE_AUTHORITY_SEED.as_bytes(), exchange_name.as_bytes()],
+			bump,
+	)]
+	pub exchange_authority: UncheckedAccount<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
+		bump
+	)]
+	pub available_asset: Account<'info, AvailableAsset>,
+	#[account(
+		seeds = [asset_name.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange_reserve_token: Box<Account<'info, TokenAccount>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), collateral_asset_name.as_bytes()],
+		bump
+	)]
+	pub collateral_available_asset: Account<'info, AvailableAsset>,
+	#[account(
+		mut,
+		seeds = [collateral_asset_name.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub collateral_exchange_reserve_token: Box<Account<'info, TokenAccount>>,
+	// Programs and Sysvars
+	pub system_program: Program<'info, System>,
+	pub token_program: Program<'info, Token>,
+	pub rent: Sysvar<'info, Rent>,
+}
+
+// update the funding rate for the collateral asset
+// get the price of the asset and the collateral
+// check that the position is under collateralized
+// take the margin and funding fees for closing the position
+// pay the liquidator the liquidation fee in the collateral token
+// release the reserves and leave the remaining collateral in the pool
+// update the global short size and average price for shorts
+// close the position and return the rent to the position owner
+pub fn handler(
+	ctx: Context<LiquidatePosition>,
+	_exchange_name: String,
+	_asset_name: String,
+	_collateral_asset_name: String,
+	is_long: bool
+) -> ProgramResult {
+	require!(
+		ctx.remaining_accounts.len() / 4 == ctx.accounts.exchange.assets.len(),
+		ErrorCode::MissingWhitelistedAssets
+	);
+	validate_collateral(
+		is_long,
+		&ctx.accounts.available_asset,
+		&ctx.accounts.collateral_available_asset
+	)?;
+	require!(
+		ctx.accounts.position.collateral_mint == ctx.accounts.collateral_available_asset.mint_address,
+		ErrorCode::InvalidCollateral
+	);
+	let now = Clock::get()?.unix_timestamp;
+	update_cumulative_funding_rate(&mut ctx.accounts.collateral_available_asset, now)?;
+	let position = &ctx.accounts.position;
+	require!(position.size > 0, ErrorCode::InvalidPositionDelta);
+
+	let (_, _, oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
+		ctx.remaining_accounts,
+		&ctx.accounts.exchange_reserve_token
+	)?;
+	let price = normalize_price(oracle_price.price, oracle_price.exponent)?;
+	let (_, _, collateral_oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
+		ctx.remaining_accounts,
+		&ctx.accounts.collateral_exchange_reserve_token
+	)?;
+	let collateral_price = normalize_price(collateral_oracle_price.price, collateral_oracle_price.exponent)?;
+	require!(price > 0 && collateral_price > 0, ErrorCode::InvalidOracle);
+
+	let token_decimals = get_token_decimals(&ctx.accounts.available_asset)?;
+	let collateral_decimals = get_token_decimals(&ctx.accounts.collateral_available_asset)?;
+
+	let exchange = &ctx.accounts.exchange;
+	let (has_profit, delta) = get_delta(
+		is_long,
+		position.size,
+		position.average_price,
+		price,
+		token_decimals,
+		&MinProfit {
+			last_increased_time: position.last_increased_time,
+			now,
+			min_profit_time: exchange.min_profit_time,
+			min_profit_basis_points: ctx.accounts.available_asset.min_profit_basis_points,
+		}
+	)?;
+	let margin_fees = get_margin_fee(position.size, price, token_decimals, exchange.margin_fee_basis_points)?
+		.checked_add(get_funding_fee(
+			position.size,
+			price,
+			token_decimals,
+			position.entry_funding_rate,
+			ctx.accounts.collateral_available_asset.cumulative_funding_rate
+		)?)
+		.ok_or(ErrorCode::MathOverflow)?;
+	let liquidation_fee = exchange.liquidation_fee_usd.checked_mul(PRICE_DECIMALS_FACTOR).ok_or(ErrorCode::MathOverflow)?;
+	require!(
+		should_liquidate(
+			tokens_to_usd(position.size, price, token_decimals)?,
+			position.collateral,
+			has_profit,
+			delta,
+			margin_fees,
+			liquidation_fee
+		)?,
+		ErrorCode::PositionNotLiquidatable
+	);
+	verbose_msg!("liquidating position collateral {} delta {} margin fees {}", position.collateral, delta, margin_fees);
+
+	let (fee_tokens, liquidator_tokens, collateral_tokens) = get_liquidation_token_amounts(
+		position.collateral,
+		margin_fees,
+		liquidation_fee,
+		collateral_price,
+		collateral_decimals
+	)?;
+	let collateral = position.collateral;
+	let reserve_amount = position.reserve_amount;
+	let size = position.size;
+	let average_price = position.average_price;
+
+	let collateral_asset = &mut ctx.accounts.collateral_available_asset;
+	collateral_asset.occupied_reserves = collateral_asset.occupied_reserves.checked_sub(reserve_amount).ok_or(ErrorCode::MathOverflow)?;
+	collateral_asset.fee_reserves = collateral_asset.fee_reserves.checked_add(fee_tokens).ok_or(ErrorCode::MathOverflow)?;
+	// short collateral is held aside from the pool, so whatever is left of it goes to the pool
+	let pool_reserves = if is_long {
+		collateral_asset.pool_reserves
+	} else {
+		collateral_asset.pool_reserves.checked_add(collateral_tokens).ok_or(ErrorCode::MathOverflow)?
+	};
+	collateral_asset.pool_reserves = pool_reserves
+		.checked_sub(fee_tokens)
+		.ok_or(ErrorCode::MathOverflow)?
+		.checked_sub(liquidator_tokens)
+		.ok_or(ErrorCode::InsufficientReserves)?;
+	if is_long {
+		// the pool keeps the remaining collateral and no longer owes the long its size
+		collateral_asset.net_protocol_liabilities = collateral_asset.net_protocol_liabilities
+			.checked_add(collateral)
+			.ok_or(ErrorCode::MathOverflow)?
+			.saturating_sub(tokens_to_usd(size, average_price, token_decimals)?);
+	}
+	verbose_msg!("liquidator_tokens {} fee_tokens {} pool reserves {}", liquidator_tokens, fee_tokens, collateral_asset.pool_reserves);
+
+	if !is_long {
+		let asset = &mut ctx.accounts.available_asset;
+		asset.global_short_average_price = get_global_short_average_price_after_decrease(
+			asset.global_short_size,
+			asset.global_short_average_price,
+			size,
+			average_price
+		)?;
+		asset.global_short_size = asset.global_short_size.checked_sub(size).ok_or(ErrorCode::MathOverflow)?;
+	}
+	sync_collateral_available_asset(&mut ctx.accounts.available_asset, &ctx.accounts.collateral_available_asset);
+
+	let exchange_auth_bump = match ctx.bumps.get("exchange_authority") {
+			Some(bump) => {
+					bump
+			}
+			None => {
+					msg!("Wrong bump key. Available keys are {:?}", ctx.bumps.keys());
+					return Err(ErrorCode::InvalidBump.into());
+			}
+	};
+
+	let exchange_name = ctx.accounts.exchange.name.as_ref();
+	let seeds = exchange_authority_seeds!(
+			exchange_name = exchange_name,
+			bump = *exchange_auth_bump
+	);
+	let signer = &[&seeds[..]];
+
+	token::transfer(
+		ctx.accounts.into_transfer_context(signer),
+		liquidator_tokens,
+	)?;
+
+	emit!(Liquidated {
+		owner: ctx.accounts.position.owner,
+		position: ctx.accounts.position.key(),
+		liquidator: ctx.accounts.liquidator.key(),
+		mint: ctx.accounts.available_asset.mint_address,
+		collateral_mint: ctx.accounts.collateral_available_asset.mint_address,
+		is_long,
+		size,
+		collateral,
+		normalized_price: price,
+		margin_fees,
+		liquidator_amount: liquidator_tokens,
+		pool_reserves: ctx.accounts.collateral_available_asset.pool_reserves,
+		occupied_reserves: ctx.accounts.collateral_available_asset.occupied_reserves,
+	});
+	Ok(())
+}
+
+/// Converts the margin fees, the liquidation fee and the collateral of a liquidated position
+/// from USD into the collateral token, the margin fees are rounded up in favor of the pool
+pub fn get_liquidation_token_amounts(
+	collateral: u64,
+	margin_fees: u64,
+	liquidation_fee: u64,
+	collateral_price: u64,
+	collateral_decimals: u8
+) -> Result<(u64, u64, u64)> {
+	Ok((
+		usd_to_tokens_round_up(margin_fees, collateral_price, collateral_decimals)?,
+		usd_to_tokens(liquidation_fee, collateral_price, collateral_decimals)?,
+		usd_to_tokens(collateral, collateral_price, collateral_decimals)?
+	))
+}
+
+/// A position can be liquidated when its losses exceed its collateral, when the collateral
+/// left after losses cannot cover the fees for closing the position and paying the liquidator,
+/// or when the remaining collateral no longer meets the maintenance margin of MAX_LEVERAGE
+pub fn should_liquidate(
+	size_usd: u64,
+	collateral: u64,
+	has_profit: bool,
+	delta: u64,
+	margin_fees: u64,
+	liquidation_fee: u64
+) -> Result<bool> {
+	if !has_profit && collateral < delta {
+		return Ok(true);
+	}
+	let remaining_collateral = if has_profit { collateral } else { collateral - delta };
+	if remaining_collateral < margin_fees.checked_add(liquidation_fee).ok_or(ErrorCode::MathOverflow)? {
+		return Ok(true);
+	}
+	Ok((remaining_collateral as u128) * (MAX_LEVERAGE as u128) < size_usd as u128)
+}
+
+impl<'info> LiquidatePosition<'info> {
+	pub fn into_transfer_context<'a, 'b, 'c>(
+		&self,
+		signer: &'a [&'b [&'c [u8]]]
+	) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+			let cpi_accounts = Transfer {
+					from: self.collateral_exchange_reserve_token.to_account_info(),
+					to: self.liquidator_token.to_account_info(),
+					authority: self.exchange_authority.to_account_info(),
+			};
+			let cpi_program = self.token_program.to_account_info();
+			CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn healthy_position_is_not_liquidated() {
+		assert!(!should_liquidate(10_000, 1_000, false, 100, 10, 40).unwrap());
+	}
+
+	#[test]
+	fn losses_exceeding_collateral_are_liquidated() {
+		assert!(should_liquidate(10_000, 1_000, false, 1_001, 10, 40).unwrap());
+	}
+
+	#[test]
+	fn collateral_not_covering_fees_is_liquidated() {
+		assert!(should_liquidate(10_000, 1_000, false, 960, 10, 40).unwrap());
+	}
+
+	#[test]
+	fn collateral_below_maintenance_margin_is_liquidated() {
+		assert!(should_liquidate(100_000, 1_000, false, 900, 10, 40).unwrap());
+		assert!(!should_liquidate(100_000, 5_000, true, 900, 10, 40).unwrap());
+	}
+
+	#[test]
+	fn liquidation_amounts_use_the_decimals_of_the_collateral() {
+		// $100 of collateral, $0.5 of margin fees and a $5 liquidation fee
+		// paid in USDC with 6 decimals at $1
+		assert_eq!(
+			get_liquidation_token_amounts(100_000_000, 500_000, 5_000_000, 1_000_000, 6).unwrap(),
+			(500_000, 5_000_000, 100_000_000)
+		);
+		// and in SOL with 9 decimals at $25
+		assert_eq!(
+			get_liquidation_token_amounts(100_000_000, 500_000, 5_000_000, 25_000_000, 9).unwrap(),
+			(20_000_000, 200_000_000, 4_000_000_000)
+		);
+		// fees that are worth less than a lamport still cost one
+		assert_eq!(get_liquidation_token_amounts(0, 1, 0, 25_000_000, 0).unwrap(), (1, 0, 0));
+	}
+}
@@ -1,5 +1,3 @@
-use std::ops::{Sub, Add, Div, Mul};
-
 use anchor_spl::token::{Mint, Token, TokenAccount};
 use pyth_client::{PriceType};
 use solana_program::program_pack::Pack;
@@ -7,9 +5,14 @@ use spl_token::state::Account as SPLTokenAccount;
 use anchor_lang::prelude::*;
 use crate::constants::*;
 use crate::*;
-use crate::error::{ErrorCode};
-use std::cmp::max;
+use crate::error::ErrorCode;
+use std::cmp::min;
 use std::convert::TryInto;
+use increase_position::normalize_price;
+use decrease_position::{get_delta, MinProfit};
+use crate::oracle::{get_asset_price, OraclePrice};
+use crate::events::LpMinted;
+use jmx_math::{mul_div, mul_div_basis_points, pow10, Price, Rounding, TokenAmount, Usd};
 
 // need to check that the mint provided matches the redeemable mint
 // CHECK: that mints and provided assets match for all provided accounts
@@ -62,80 +65,35 @@ pub struct MintLpToken<'info> {
 }
 
 // CHECK: need to check that oracle account provided matches oracle account in available asset
-// CHECK: need to evauluate the max amount of the provided token we will accept and not go over that
-pub fn handler(ctx: Context<MintLpToken>, exchange_name: String, asset_name: String, lamports: u64) -> ProgramResult {
-	assert!(
-		ctx.remaining_accounts.len() / 2 == ctx.accounts.exchange.assets.len(), 
-		"must supply all whitelisted assets when minting"
-	);
-	assert!(lamports > 100, "too few lamports for transaction");
-
-	// transfer lamports from user to reserve_asset_token_acount
-	let exchange_reserve_token = &ctx.accounts.exchange_reserve_token;
-	msg!("lamports {}", lamports);
+pub fn handler(ctx: Context<MintLpToken>, exchange_name: String, asset_name: String, lamports: u64, min_lp_out: u64) -> ProgramResult {
+	validate_mint_lp(&ctx.accounts.exchange, &ctx.accounts.available_asset, ctx.remaining_accounts, lamports)?;
+	verbose_msg!("lamports {}", lamports);
 
-	let (aum, precise_price, exponent) = calculate_aum(
+	// mints are priced at the max aum and the deposit at the min price so new LPs cannot dilute existing ones
+	let (_, aum, oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
 		ctx.remaining_accounts, 
-		exchange_reserve_token,
-		&ctx.accounts.exchange.price_oracles
-	).unwrap();
-
-	msg!("precise price {}", precise_price);
-	let lp_mint = &ctx.accounts.lp_mint;
-	msg!("lp_mint {:?}", lp_mint.key());
-
-	let lp_mint_supply = lp_mint.supply;
-	let mut price_per_lp_token_numerator = 1;
-	let mut price_per_lp_token_denominator = 1;
-	let mut total_fee_in_basis_points = BASIS_POINTS_PRECISION as u64;
-
-	if lp_mint_supply > 0 {
-		msg!("we have current aum:{:?} mint_supply: {:?}", aum, lp_mint_supply);
-		total_fee_in_basis_points = calculate_fee_basis_points(
-			aum,
-			&ctx.accounts.available_asset,
-			ctx.accounts.exchange.total_weights,
-			precise_price,
-			exponent,
-			lamports,
-			true
-		);
-		// msg!("FEE_IN_BASIS_POINTS {}", FEE_IN_BASIS_POINTS);
-		msg!("total_fee_in_basis_points {}", total_fee_in_basis_points);
-		let raw_bps_to_charge = total_fee_in_basis_points.checked_sub(BASIS_POINTS_PRECISION as u64).unwrap();
-		price_per_lp_token_numerator = lp_mint_supply.checked_mul(BASIS_POINTS_PRECISION - raw_bps_to_charge as u64)
-		.unwrap();
-		
-		price_per_lp_token_denominator = aum.checked_mul(BASIS_POINTS_PRECISION)
-				.unwrap();
-		msg!("price_per_lp_token_numerator {} price_per_lp_token_denominator {}", price_per_lp_token_numerator, price_per_lp_token_denominator);
-		msg!("raw_bps_to_charge {}", raw_bps_to_charge)
-	}
-	
-	msg!("precise_price {}", precise_price);
-	let usd_value_of_deposit = precise_price.
-		checked_mul(lamports).
-		unwrap().
-		checked_div(10_u128.pow(exponent as u32) as u64).
-		unwrap();
-	
-	// msg!("numerator price_per_lp_token_numerator {}", price_per_lp_token_numerator);
-	// msg!("denom price_per_lp_token_denominator {}", price_per_lp_token_denominator);
-	msg!("usd_value_of_deposit {}", usd_value_of_deposit);
-	let amount_of_glp_to_mint = (usd_value_of_deposit as u128).
-		checked_mul(price_per_lp_token_numerator as u128).
-		unwrap().
-		checked_div(price_per_lp_token_denominator as u128).
-		unwrap() as u64;
-
-	msg!("amount_of_glp_to_mint {}", amount_of_glp_to_mint);
+		&ctx.accounts.exchange_reserve_token
+	)?;
+	verbose_msg!("lp_mint {:?}", ctx.accounts.lp_mint.key());
+
+	let quote = apply_mint_lp(
+		&ctx.accounts.exchange,
+		&mut ctx.accounts.available_asset,
+		ctx.accounts.lp_mint.supply,
+		aum,
+		oracle_price.min_price()?,
+		oracle_price.exponent,
+		lamports
+	)?;
+	require!(quote.lp_amount >= min_lp_out, ErrorCode::SlippageExceeded);
 	let exchange_auth_bump = match ctx.bumps.get("exchange_authority") {
 			Some(bump) => {
 					bump
 			}
 			None => {
 					msg!("Wrong bump key. Available keys are {:?}", ctx.bumps.keys());
-					panic!("Wrong bump key")
+					return Err(ErrorCode::InvalidBump.into());
 			}
 	};
 
@@ -151,20 +109,135 @@ pub fn handler(ctx: Context<MintLpToken>, exchange_name: String, asset_name: Str
 		lamports as u64,
 	)?;
 
-	token::mint_to(ctx.accounts.into_mint_to_context(signer), amount_of_glp_to_mint as u64)?;
-	// update reserve amounts on available asset
-	let asset = &mut ctx.accounts.available_asset;
-	let new_pool_reserves = lamports.
-	checked_mul(BASIS_POINTS_PRECISION as u64).
-	unwrap().
-	checked_div(total_fee_in_basis_points).
-	unwrap();
-
-	asset.pool_reserves += new_pool_reserves;
-	asset.fee_reserves += lamports - new_pool_reserves;
+	token::mint_to(ctx.accounts.into_mint_to_context(signer), quote.lp_amount)?;
+	emit!(LpMinted {
+		user: ctx.accounts.user_authority.key(),
+		mint: ctx.accounts.available_asset.mint_address,
+		amount_in: lamports,
+		lp_amount: quote.lp_amount,
+		price: quote.price,
+		exponent: quote.exponent,
+		fee_basis_points: quote.fee_basis_points,
+		aum: quote.aum,
+		pool_reserves: quote.pool_reserves,
+		fee_reserves: quote.fee_reserves,
+	});
 	Ok(())
 }
 
+/// Result of a mint, returned as return data by quote_mint_lp. Prices are the oracle price
+/// in units of 10^-exponent USD and the reserves are the ones after the mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct MintLpQuote {
+	pub lp_amount: u64,
+	pub fee_basis_points: u64,
+	pub price: u64,
+	pub exponent: u64,
+	pub aum: u64,
+	pub pool_reserves: u64,
+	pub fee_reserves: u64,
+}
+
+pub fn validate_mint_lp(
+	exchange: &Exchange,
+	available_asset: &AvailableAsset,
+	remaining_accounts: &[AccountInfo],
+	lamports: u64
+) -> Result<()> {
+	require!(exchange.assets.contains(&available_asset.mint_address), ErrorCode::AssetNotWhitelisted);
+	require!(!exchange.paused, ErrorCode::ExchangePaused);
+	require!(!available_asset.mint_paused, ErrorCode::ActionPaused);
+	require!(!available_asset.withdraw_only, ErrorCode::AssetWithdrawOnly);
+	require!(
+		remaining_accounts.len() / 4 == exchange.assets.len(), 
+		ErrorCode::MissingWhitelistedAssets
+	);
+	require!(lamports > 100, ErrorCode::AmountTooSmall);
+	Ok(())
+}
+
+/// Prices a deposit of lamports of the asset at precise_price against the aum and the lp supply and
+/// moves it into the pool and fee reserves of the asset.
+/// mint_lp_token applies it to the account, quote_mint_lp to a copy of it
+pub fn apply_mint_lp(
+	exchange: &Exchange,
+	available_asset: &mut AvailableAsset,
+	lp_mint_supply: u64,
+	aum: u64,
+	precise_price: u64,
+	exponent: u64,
+	lamports: u64
+) -> Result<MintLpQuote> {
+	verbose_msg!("precise price {}", precise_price);
+	let usd_value_of_deposit = get_usd_value(available_asset, lamports, precise_price, exponent)?;
+	verbose_msg!("usd_value_of_deposit {}", usd_value_of_deposit);
+	// the first deposit mints one whole LP token per USD
+	let mut amount_of_glp_to_mint = usd_value_of_deposit
+		.checked_mul(pow10((LP_DECIMALS - PRICE_DECIMALS) as u32).map_err(ErrorCode::from)?)
+		.ok_or(ErrorCode::MathOverflow)?;
+	let mut total_fee_in_basis_points = BASIS_POINTS_PRECISION as u64;
+
+	if lp_mint_supply > 0 {
+		verbose_msg!("we have current aum:{:?} mint_supply: {:?}", aum, lp_mint_supply);
+		total_fee_in_basis_points = calculate_fee_basis_points(
+			aum,
+			available_asset,
+			exchange.total_weights,
+			precise_price,
+			exponent,
+			&ReserveChange {
+				amount: lamports,
+				increment: true,
+				fee_basis_points: exchange.mint_burn_basis_points,
+				tax_basis_points: exchange.tax_basis_points,
+			}
+		)?;
+		verbose_msg!("total_fee_in_basis_points {}", total_fee_in_basis_points);
+		let raw_bps_to_charge = total_fee_in_basis_points.checked_sub(BASIS_POINTS_PRECISION as u64).ok_or(ErrorCode::MathOverflow)?;
+		verbose_msg!("raw_bps_to_charge {}", raw_bps_to_charge);
+		// the deposit net of fees buys its share of the aum, the products are taken in u128
+		amount_of_glp_to_mint = mul_div_basis_points(
+			usd_value_of_deposit,
+			lp_mint_supply,
+			aum,
+			BASIS_POINTS_PRECISION.checked_sub(raw_bps_to_charge).ok_or(ErrorCode::MathOverflow)?,
+			Rounding::Down
+		).map_err(ErrorCode::from)?;
+	}
+	verbose_msg!("amount_of_glp_to_mint {}", amount_of_glp_to_mint);
+
+	// update reserve amounts on available asset
+	let new_pool_reserves = mul_div(lamports, BASIS_POINTS_PRECISION, total_fee_in_basis_points, Rounding::Down)
+		.map_err(ErrorCode::from)?;
+	available_asset.pool_reserves = available_asset.pool_reserves.checked_add(new_pool_reserves).ok_or(ErrorCode::MathOverflow)?;
+	available_asset.fee_reserves = available_asset.fee_reserves
+		.checked_add(lamports - new_pool_reserves)
+		.ok_or(ErrorCode::MathOverflow)?;
+	increase_usd_debt(available_asset, new_pool_reserves, precise_price, exponent)?;
+	verbose_msg!("pool reserves {} usd debt {}", available_asset.pool_reserves, available_asset.usd_debt);
+	validate_max_reserves(available_asset)?;
+
+	Ok(MintLpQuote {
+		lp_amount: amount_of_glp_to_mint,
+		fee_basis_points: total_fee_in_basis_points - BASIS_POINTS_PRECISION,
+		price: precise_price,
+		exponent,
+		aum,
+		pool_reserves: available_asset.pool_reserves,
+		fee_reserves: available_asset.fee_reserves,
+	})
+}
+
+/// An amount of an asset joining (increment) or leaving the pool reserves, with the base fee
+/// and the max tax charged on it by calculate_fee_basis_points
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReserveChange {
+	pub amount: u64,
+	pub increment: bool,
+	pub fee_basis_points: u64,
+	pub tax_basis_points: u64,
+}
+
 // cases to consider
 // 1. initialAmount is far from targetAmount, action increases balance slightly => high rebate
 // 2. initialAmount is far from targetAmount, action increases balance largely => high rebate
@@ -174,7 +247,6 @@ pub fn handler(ctx: Context<MintLpToken>, exchange_name: String, asset_name: Str
 // 6. initialAmount is close to targetAmount, action reduces balance largely => low tax
 // 7. initialAmount is above targetAmount, nextAmount is below targetAmount and vice versa
 // 8. a large swap should have similar fees as the same trade split into multiple smaller swaps
-/// CHECK: types here are bad, and conversions too many, need to consolidate
 /// CHECK: that we are doing the correct math when calculating
 /// fees that should be charged 
 /// CHECK: that we are calculating available assets correctly
@@ -186,150 +258,236 @@ pub fn calculate_fee_basis_points(
 	total_weight: u64, 
 	price: u64,
 	exponent: u64,
-	new_amount: u64,
-	increment: bool
-) -> u64 {
+	change: &ReserveChange) -> Result<u64> {
+	let ReserveChange { amount: new_amount, increment, fee_basis_points, tax_basis_points } = *change;
+	let base_fee_in_basis_points = BASIS_POINTS_PRECISION.
+		checked_add(fee_basis_points).
+		ok_or(ErrorCode::MathOverflow)?;
 	let current_reserves = available_asset.pool_reserves;
-	msg!("price {}", price);
-	msg!("exponent in calc fee bps {}", exponent);
-	let initial_reserve_usd_value = (current_reserves).
-		checked_mul(price as u64).
-		unwrap().
-		checked_div(10_i64.pow(exponent as u32) as u64)
-		.unwrap();
-
-	let diff_usd_value = new_amount.
-		checked_mul(price as u64).
-		unwrap().
-		checked_div(10_u128.pow(exponent as u32) as u64)
-		.unwrap();
-
-	msg!("new amount {}", new_amount);
+	verbose_msg!("price {}", price);
+	verbose_msg!("exponent in calc fee bps {}", exponent);
+	let initial_reserve_usd_value = Usd(get_usd_value(available_asset, current_reserves, price, exponent)?);
+	let diff_usd_value = Usd(get_usd_value(available_asset, new_amount, price, exponent)?);
+
+	verbose_msg!("new amount {}", new_amount);
 	let next_reserve_usd_value = if increment { 
-		msg!("next reserve value {}", initial_reserve_usd_value + diff_usd_value);
-		initial_reserve_usd_value + diff_usd_value 
+		initial_reserve_usd_value.checked_add(diff_usd_value).map_err(ErrorCode::from)?
 	} else { 
-		msg!("maxxx initial_reserve_usd_value - diff_usd_value {}", initial_reserve_usd_value - diff_usd_value);
-		max((initial_reserve_usd_value - diff_usd_value) as i64, 0 as i64) as u64
+		initial_reserve_usd_value.saturating_sub(diff_usd_value)
 	};
 	
-	msg!("cur token weight {}", available_asset.token_weight);
-	msg!("total weights {}", total_weight);
-	let target_lp_usd_value = available_asset.token_weight.
-		checked_mul(aum).
-		unwrap().
-		checked_div(total_weight).
-		unwrap();
-
-	msg!("diff_usd_value {}", diff_usd_value);
-	msg!("current_reserves {}", current_reserves);
-	msg!("initial_reserve_usd_value {}", initial_reserve_usd_value);
-	msg!("next_reserve_usd_value {}", next_reserve_usd_value );
-	msg!("target_lp_usd_value {}", target_lp_usd_value);
-	if target_lp_usd_value == 0 {
-		msg!("returning fee in basis points");
-		return FEE_IN_BASIS_POINTS as u64;
+	verbose_msg!("cur token weight {}", available_asset.token_weight);
+	verbose_msg!("total weights {}", total_weight);
+	let target_lp_usd_value = Usd(mul_div(available_asset.token_weight, aum, total_weight, Rounding::Down).map_err(ErrorCode::from)?);
+
+	verbose_msg!("diff_usd_value {:?}", diff_usd_value);
+	verbose_msg!("current_reserves {}", current_reserves);
+	verbose_msg!("initial_reserve_usd_value {:?}", initial_reserve_usd_value);
+	verbose_msg!("next_reserve_usd_value {:?}", next_reserve_usd_value);
+	verbose_msg!("target_lp_usd_value {:?}", target_lp_usd_value);
+	if target_lp_usd_value == Usd::ZERO {
+		verbose_msg!("returning fee in basis points");
+		return Ok(base_fee_in_basis_points);
 	}
 
-	let initial_usd_from_target = if initial_reserve_usd_value > target_lp_usd_value { 
-		(initial_reserve_usd_value - target_lp_usd_value) as i64
-	} else { (target_lp_usd_value - initial_reserve_usd_value) as i64 };
-
-	let next_usd_from_target = if next_reserve_usd_value > target_lp_usd_value { 
-		(next_reserve_usd_value - target_lp_usd_value) as i64
-	} else { (target_lp_usd_value - next_reserve_usd_value) as i64 };
+	let initial_usd_from_target = initial_reserve_usd_value.abs_diff(target_lp_usd_value);
+	let next_usd_from_target = next_reserve_usd_value.abs_diff(target_lp_usd_value);
 
 	// action improves target balance
 	if next_usd_from_target < initial_usd_from_target {
-		msg!("next_usd_from_target {} initial_usd_from_target {}", next_usd_from_target, initial_usd_from_target);
-		let rebate_bps = (FEE_IN_BASIS_POINTS as i64).
-			checked_sub(BASIS_POINTS_PRECISION as i64).
-			unwrap().
-			checked_mul(initial_usd_from_target).
-			unwrap().
-			checked_div(target_lp_usd_value as i64).
-			unwrap();
-		msg!("rebate bps {} initial_usd_from_target {} target_lp_usd_value {}", rebate_bps, initial_usd_from_target, target_lp_usd_value);
-		return if rebate_bps >= FEE_RAW as i64 {
-			msg!("returning precision");
-			BASIS_POINTS_PRECISION as u64
-		} else { 
-			msg!("returning (FEE_IN_BASIS_POINTS as i64).sub(rebate_bps ) {}", (FEE_IN_BASIS_POINTS as i64).sub(rebate_bps ));
-			(FEE_IN_BASIS_POINTS as i64).sub(rebate_bps ) as u64 
-		};
+		verbose_msg!("next_usd_from_target {:?} initial_usd_from_target {:?}", next_usd_from_target, initial_usd_from_target);
+		let rebate_bps = mul_div(fee_basis_points, initial_usd_from_target.0, target_lp_usd_value.0, Rounding::Down)
+			.map_err(ErrorCode::from)?;
+		verbose_msg!("rebate bps {}", rebate_bps);
+		return Ok(base_fee_in_basis_points.saturating_sub(rebate_bps).max(BASIS_POINTS_PRECISION));
 	} else if next_usd_from_target == initial_usd_from_target {
-		return FEE_IN_BASIS_POINTS
+		return Ok(base_fee_in_basis_points)
 	}
 
-	let mut average_diff = initial_usd_from_target.add(next_usd_from_target).div(2);
-	msg!("average_diff {}", average_diff);
-	if average_diff > target_lp_usd_value as i64{
-		average_diff = target_lp_usd_value as i64;
-	}
+	// the average distance from the target is capped at the target so the tax never exceeds tax_basis_points
+	let average_diff = min(
+		initial_usd_from_target.checked_add(next_usd_from_target).map_err(ErrorCode::from)?.0 / 2,
+		target_lp_usd_value.0
+	);
+	verbose_msg!("average_diff {}", average_diff);
+	let penalty = mul_div(tax_basis_points, average_diff, target_lp_usd_value.0, Rounding::Down).map_err(ErrorCode::from)?;
+	Ok(base_fee_in_basis_points.checked_add(penalty).ok_or(ErrorCode::MathOverflow)?)
+}
+
+/// USD value normalized with PRICE_DECIMALS of an amount of the asset, scaled by the decimals of its
+/// mint so assets with different decimals are valued in the same unit
+pub fn get_usd_value(available_asset: &AvailableAsset, amount: u64, precise_price: u64, exponent: u64) -> Result<u64> {
+	Ok(TokenAmount(amount)
+		.to_usd(Price(normalize_price(precise_price, exponent)?), get_token_decimals(available_asset)?, Rounding::Down)
+		.map_err(ErrorCode::from)?
+		.0)
+}
+
+/// Amount of the asset worth a USD value normalized with PRICE_DECIMALS, rounded down in favor of the pool
+pub fn get_token_amount(available_asset: &AvailableAsset, usd: u64, precise_price: u64, exponent: u64) -> Result<u64> {
+	Ok(Usd(usd)
+		.to_tokens(Price(normalize_price(precise_price, exponent)?), get_token_decimals(available_asset)?, Rounding::Down)
+		.map_err(ErrorCode::from)?
+		.0)
+}
+
+pub fn get_token_decimals(available_asset: &AvailableAsset) -> Result<u8> {
+	Ok(available_asset.token_decimals.try_into().map_err(|_| ErrorCode::InvalidTokenDecimals)?)
+}
+
+/// Adds the USD value of the amount of the asset to the usd debt of the asset
+pub fn increase_usd_debt(available_asset: &mut AvailableAsset, amount: u64, precise_price: u64, exponent: u64) -> Result<()> {
+	let usd = Usd(get_usd_value(available_asset, amount, precise_price, exponent)?);
+	available_asset.usd_debt = Usd(available_asset.usd_debt).checked_add(usd).map_err(ErrorCode::from)?.0;
+	Ok(())
+}
 
-	let penalty = (PENALTY_IN_BASIS_POINTS as i64).mul(average_diff).div(target_lp_usd_value as i64);
-	return (FEE_IN_BASIS_POINTS as u64).add(penalty as u64)
+/// Removes the USD value of the amount of the asset from the usd debt of the asset, the debt
+/// was recorded at past prices so it floors at zero rather than underflowing
+pub fn decrease_usd_debt(available_asset: &mut AvailableAsset, amount: u64, precise_price: u64, exponent: u64) -> Result<()> {
+	let usd = Usd(get_usd_value(available_asset, amount, precise_price, exponent)?);
+	available_asset.usd_debt = Usd(available_asset.usd_debt).saturating_sub(usd).0;
+	Ok(())
 }
 
-// CHECK: that we should take the value of the token account as AUM and not the general reserves from the
-// available asset account
+/// Rejects deposits that push the pool reserves or usd debt of the asset over their caps, a cap of 0 is no cap
+pub fn validate_max_reserves(available_asset: &AvailableAsset) -> Result<()> {
+	require!(
+		available_asset.max_lptoken_amount == 0 || available_asset.pool_reserves <= available_asset.max_lptoken_amount,
+		ErrorCode::MaxReservesExceeded
+	);
+	require!(
+		available_asset.max_usd_debt == 0 || available_asset.usd_debt <= available_asset.max_usd_debt,
+		ErrorCode::MaxUsdDebtExceeded
+	);
+	Ok(())
+}
+
+/// remaining_accounts are expected in groups of [reserve token account, price oracle, backup price oracle,
+/// available asset] for every whitelisted asset of the exchange, each asset exactly once.
+/// AUM is valued from the accounting on each available asset instead of the reserve token balances, so
+/// fees and short collateral sitting in the token accounts are not counted as LP assets. Longs are valued
+/// at the USD guaranteed to them plus the unreserved pool, and open short pnl is owed to or by the pool.
+/// Returns (min aum, max aum, oracle price of the reserve token) where min and max aum use the min
+/// and max prices of every asset and are USD normalized with PRICE_DECIMALS
 pub fn calculate_aum(
+	exchange: &Account<Exchange>,
 	remaining_accounts: &[AccountInfo], 
-	exchange_reserve_token: &Box<anchor_lang::prelude::Account<'_, TokenAccount>>,
-	price_oracles: &Vec<anchor_lang::prelude::Pubkey>
-) -> Result<(u64,u64,u64)> {
-	for o in price_oracles.iter() {
-		msg!("{}", o);
+	exchange_reserve_token: &Box<anchor_lang::prelude::Account<'_, TokenAccount>>
+) -> Result<(u64, u64, OraclePrice)> {
+	let (min_aum, max_aum, reserve_token_price) = calculate_min_max_aum(exchange, remaining_accounts, Some(exchange_reserve_token.mint))?;
+	let reserve_token_price = reserve_token_price.ok_or(ErrorCode::AssetNotWhitelisted)?;
+	Ok((min_aum, max_aum, reserve_token_price))
+}
+
+/// calculate_aum for callers without a reserve token, the oracle price of reserve_mint is
+/// returned when it is one of the assets in remaining_accounts
+pub fn calculate_min_max_aum(
+	exchange: &Account<Exchange>,
+	remaining_accounts: &[AccountInfo],
+	reserve_mint: Option<Pubkey>
+) -> Result<(u64, u64, Option<OraclePrice>)> {
+	require!(
+		remaining_accounts.len() == exchange.assets.len() * 4,
+		ErrorCode::MissingWhitelistedAssets
+	);
+	let mut min_aum = AumAccumulator::default();
+	let mut max_aum = AumAccumulator::default();
+	let mut reserve_token_price = None;
+	let mut counted_mints = Vec::with_capacity(exchange.assets.len());
+	for asset_accounts in remaining_accounts.chunks(4) {
+		let token_account_info = &asset_accounts[0];
+		let oracle_account_info = &asset_accounts[1];
+		let backup_oracle_account_info = &asset_accounts[2];
+		let available_asset_info = &asset_accounts[3];
+
+		// token account
+		if token_account_info.owner != &spl_token::id() {
+			return Err(ErrorCode::InvalidTokenAccount.into());
+		}
+		let token_account = SPLTokenAccount::unpack_unchecked(&token_account_info.data.borrow())?;
+
+		// available asset account
+		let available_asset = Account::<AvailableAsset>::try_from(available_asset_info)?;
+		require!(available_asset.mint_address == token_account.mint, ErrorCode::InvalidAvailableAsset);
+		validate_aum_asset(&exchange.key(), exchange, &available_asset, &counted_mints)?;
+		counted_mints.push(available_asset.mint_address);
+
+		// oracle accounts
+		let oracle_price = get_asset_price(exchange, oracle_account_info, backup_oracle_account_info, &available_asset)?;
+
+		// get price of asset to deposit
+		if Some(token_account.mint) == reserve_mint {
+			verbose_msg!("found last_token_account.mint {}", token_account.mint);
+			reserve_token_price = Some(oracle_price);
+		}
+
+		min_aum.add_asset(&available_asset, oracle_price.min_price()?, oracle_price.exponent)?;
+		max_aum.add_asset(&available_asset, oracle_price.max_price()?, oracle_price.exponent)?;
 	}
-	let mut aum = 0;
-	let mut precise_price = 0;
-	let mut exponent = 1;
-	let mut last_token_account: SPLTokenAccount = SPLTokenAccount::unpack_unchecked(&remaining_accounts[0].data.borrow())?;
-	// msg!("remaining_accounts {:?}", remaining_accounts);
-	for (i, token_account_info) in remaining_accounts.iter().enumerate() {
-		// msg!("iterating {}", i);
-		if i % 2 == 0 {
-			// token account
-			if token_account_info.owner != &spl_token::id() {
-				return Err(ErrorCode::AccountNotSystemOwned.into());
-			}
-			let unpacked_token_account = SPLTokenAccount::unpack_unchecked(&token_account_info.data.borrow())?;
-			last_token_account = unpacked_token_account;
-		} else {
-			// oracle account
-			// CHECK: need to validate pyth data better here
-			// msg!("data key {}", token_account_info.key);
-			assert!(price_oracles.contains(token_account_info.key), "invalid oracle account provided");
-
-			let pyth_price_data = &token_account_info.try_borrow_data()?;
-			// msg!("after borrow price data {}", &token_account_info.key);
-			// msg!("Pyth Price size {} account size {}", std::mem::size_of::<pyth_client::Price>(), pyth_price_data.len());
-			let pyth_price = pyth_client::cast::<pyth_client::Price>(pyth_price_data);
-			// get price of asset to deposit
-
-			// msg!("last_token_account.mint {}", last_token_account.mint);
-			// msg!("exchange_reserve_token.mint {}", exchange_reserve_token.mint);
-			if last_token_account.mint == exchange_reserve_token.mint {
-				msg!("found last_token_account.mint {}", last_token_account.mint);
-				msg!("exchange_reserve_token.mint {}", exchange_reserve_token.mint);
-				// msg!("found price oracle for reserve asset...about to set reserve asset price {}", pyth_price.agg.price);
-				exponent = pyth_price.expo.abs() as u64;
-				precise_price = pyth_price.agg.price as u64;
-				// msg!(" in calc aum w/ expo {} precise price {}", pyth_price.expo, pyth_price.agg.price)
-			}
+	let (min_aum, max_aum) = (min_aum.aum(), max_aum.aum());
+	verbose_msg!("min aum {} max aum {}", min_aum, max_aum);
+	Ok((min_aum, max_aum, reserve_token_price))
+}
+
+/// An asset only counts towards the AUM of the exchange it is listed on, when it is whitelisted
+/// there and when it was not already counted
+pub fn validate_aum_asset(
+	exchange_key: &Pubkey,
+	exchange: &Exchange,
+	available_asset: &AvailableAsset,
+	counted_mints: &[Pubkey]
+) -> Result<()> {
+	require!(available_asset.exchange == *exchange_key, ErrorCode::InvalidAssetExchange);
+	require!(exchange.assets.contains(&available_asset.mint_address), ErrorCode::AssetNotWhitelisted);
+	require!(!counted_mints.contains(&available_asset.mint_address), ErrorCode::DuplicateAsset);
+	Ok(())
+}
+
+/// Running total of the AUM of the pool at one side of the oracle prices
+#[derive(Default)]
+pub struct AumAccumulator {
+	aum: u64,
+	short_profits: u64,
+}
+
+impl AumAccumulator {
+	pub fn add_asset(&mut self, available_asset: &AvailableAsset, precise_price: u64, exponent: u64) -> Result<()> {
+		let value = |amount: u64| get_usd_value(available_asset, amount, precise_price, exponent);
 
-			let price = pyth_price.agg.price;
-			// msg!("about to add to aum in calc aum");
-			aum += last_token_account.amount.checked_mul(price as u64)
-				.unwrap()
-				.checked_div(
-					10_u64.pow(pyth_price.expo.abs() as u32)
-				)
-				.unwrap();
-			// msg!("about to return in calc aum");
+		if available_asset.stable_token {
+			self.aum = self.aum.checked_add(value(available_asset.pool_reserves)?).ok_or(ErrorCode::MathOverflow)?;
+			return Ok(());
 		}
+
+		// shorts in profit are owed by the pool and shorts in loss are owed to it
+		let (shorts_in_profit, short_delta) = get_delta(
+			false,
+			available_asset.global_short_size,
+			available_asset.global_short_average_price,
+			normalize_price(precise_price, exponent)?,
+			get_token_decimals(available_asset)?,
+			&MinProfit::default()
+		)?;
+		if shorts_in_profit {
+			self.short_profits = self.short_profits.checked_add(short_delta).ok_or(ErrorCode::MathOverflow)?;
+		} else {
+			self.aum = self.aum.checked_add(short_delta).ok_or(ErrorCode::MathOverflow)?;
+		}
+
+		// reserved assets belong to longs, which are owed their size less their collateral
+		let unreserved = available_asset.pool_reserves.saturating_sub(available_asset.occupied_reserves);
+		self.aum = self.aum
+			.checked_add(available_asset.net_protocol_liabilities)
+			.ok_or(ErrorCode::MathOverflow)?
+			.checked_add(value(unreserved)?)
+			.ok_or(ErrorCode::MathOverflow)?;
+		Ok(())
+	}
+
+	pub fn aum(&self) -> u64 {
+		self.aum.saturating_sub(self.short_profits)
 	}
-	Ok((aum, precise_price, exponent))
 }
 
 impl<'info> MintLpToken<'info> {
@@ -360,8 +518,21 @@ impl<'info> MintLpToken<'info> {
 mod tests {
     use std::str::FromStr;
     use anchor_lang::prelude::{Pubkey};
-		use crate::calculate_fee_basis_points;
-    use crate::state::AvailableAsset;
+		use crate::{
+			calculate_fee_basis_points,
+			increase_usd_debt,
+			decrease_usd_debt,
+			validate_max_reserves,
+			get_usd_value,
+			get_token_amount,
+			apply_mint_lp,
+			apply_burn_lp,
+			validate_aum_asset,
+			AumAccumulator,
+			ReserveChange
+		};
+		use crate::constants::{LP_DECIMALS, PRICE_DECIMALS, PRICE_DECIMALS_FACTOR};
+    use crate::state::{AvailableAsset, Exchange};
 
     #[test]
     fn exploration() {
@@ -371,21 +542,35 @@ mod tests {
 		fn create_available_asset() -> AvailableAsset {
 			AvailableAsset {
 				mint_address: Pubkey::from_str("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS").unwrap(),
-				token_decimals: 1,
+				exchange: Pubkey::default(),
+				token_decimals: PRICE_DECIMALS as u64,
 				token_weight: 5,
 				min_profit_basis_points: 100,
 				max_lptoken_amount: 100,
+				max_usd_debt: 0,
 				stable_token: false,
 				shortable_token: false,
+				withdraw_only: false,
+				mint_paused: false,
+				burn_paused: false,
+				swap_in_paused: false,
+				swap_out_paused: false,
+				increase_paused: false,
+				decrease_paused: false,
 				cumulative_funding_rate: 0,
 				last_funding_time: 0,
 				oracle_address: Pubkey::from_str("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS").unwrap(),
 				backup_oracle_address: Pubkey::from_str("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS").unwrap(),
+				max_price_age_slots: 25,
+				max_price_confidence_basis_points: 100,
+				spread_basis_points: 0,
 				global_short_size: 0,
+				global_short_average_price: 0,
 				net_protocol_liabilities: 0,
 				occupied_reserves: 0,
 				fee_reserves: 0,
-				pool_reserves: 400
+				pool_reserves: 400,
+				usd_debt: 0
 			}
 		}
 
@@ -398,9 +583,8 @@ mod tests {
 					10,
 					100_0000,
 					4,
-					100,
-					true
-				);
+					&ReserveChange { amount: 100, increment: true, fee_basis_points: 30, tax_basis_points: 30 }
+				).unwrap();
 				assert_eq!(10024, fees);
     }
 
@@ -411,13 +595,12 @@ mod tests {
 
 			let fees = calculate_fee_basis_points(
 				100_000,
-				&available_asset,
+				available_asset,
 				10,
 				100_0000,
 				4,
-				100,
-				true
-			);
+				&ReserveChange { amount: 100, increment: true, fee_basis_points: 30, tax_basis_points: 30 }
+			).unwrap();
 			assert_eq!(10001, fees);
 	}
 
@@ -428,13 +611,12 @@ mod tests {
 
 		let fees = calculate_fee_basis_points(
 			100_000,
-			&available_asset,
+			available_asset,
 			10,
 			100_0000,
 			4,
-			10000,
-			true
-		);
+			&ReserveChange { amount: 10000, increment: true, fee_basis_points: 30, tax_basis_points: 30 }
+		).unwrap();
 		assert_eq!(10060, fees);
 	}
 
@@ -445,13 +627,12 @@ mod tests {
 
 		let fees = calculate_fee_basis_points(
 			100_000,
-			&available_asset,
+			available_asset,
 			10,
 			100_0000,
 			4,
-			50,
-			true
-		);
+			&ReserveChange { amount: 50, increment: true, fee_basis_points: 30, tax_basis_points: 30 }
+		).unwrap();
 		assert_eq!(10031, fees);
 	}
 
@@ -461,13 +642,12 @@ mod tests {
 			available_asset.pool_reserves = 550;
 			let fees = calculate_fee_basis_points(
 				100_000,
-				&available_asset,
+				available_asset,
 				10,
 				100_0000,
 				4,
-				10,
-				false
-			);
+				&ReserveChange { amount: 10, increment: false, fee_basis_points: 30, tax_basis_points: 30 }
+			).unwrap();
 			assert_eq!(10027, fees);
 	}
 	
@@ -478,13 +658,12 @@ mod tests {
 
 		let fees = calculate_fee_basis_points(
 			100_000,
-			&available_asset,
+			available_asset,
 			10,
 			100_0000,
 			4,
-			100,
-			false
-		);
+			&ReserveChange { amount: 100, increment: false, fee_basis_points: 30, tax_basis_points: 30 }
+		).unwrap();
 		assert_eq!(10000, fees);
 	}
 
@@ -495,13 +674,12 @@ mod tests {
 
 		let fees = calculate_fee_basis_points(
 			100_000,
-			&available_asset,
+			available_asset,
 			10,
 			100_0000,
 			4,
-			5,
-			false
-		);
+			&ReserveChange { amount: 5, increment: false, fee_basis_points: 30, tax_basis_points: 30 }
+		).unwrap();
 		assert_eq!(10059, fees);
 	}
 
@@ -512,13 +690,12 @@ mod tests {
 
 		let fees = calculate_fee_basis_points(
 			100_000,
-			&available_asset,
+			available_asset,
 			10,
 			100_0000,
 			4,
-			50,
-			false
-		);
+			&ReserveChange { amount: 50, increment: false, fee_basis_points: 30, tax_basis_points: 30 }
+		).unwrap();
 		assert_eq!(10031, fees);
 	}
 
@@ -529,13 +706,12 @@ mod tests {
 
 		let fees = calculate_fee_basis_points(
 			100_000,
-			&available_asset,
+			available_asset,
 			10,
 			100_0000,
 			4,
-			100,
-			false
-		);
+			&ReserveChange { amount: 100, increment: false, fee_basis_points: 30, tax_basis_points: 30 }
+		).unwrap();
 		assert_eq!(10030, fees);
 	}
 
@@ -546,13 +722,168 @@ mod tests {
 
 		let fees = calculate_fee_basis_points(
 			100_000,
-			&available_asset,
+			available_asset,
 			10,
 			100_0000,
 			4,
-			100,
-			true
-		);
+			&ReserveChange { amount: 100, increment: true, fee_basis_points: 30, tax_basis_points: 30 }
+		).unwrap();
 		assert_eq!(10030, fees);
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn fee_and_tax_are_configurable() {
+		let available_asset = &mut create_available_asset();
+		available_asset.pool_reserves = 500;
+
+		let fees = calculate_fee_basis_points(
+			100_000,
+			available_asset,
+			10,
+			100_0000,
+			4,
+			&ReserveChange { amount: 10000, increment: true, fee_basis_points: 15, tax_basis_points: 8 }
+		).unwrap();
+		assert_eq!(10023, fees);
+	}
+
+	#[test]
+	fn usd_debt_follows_deposits_and_withdrawals() {
+		let available_asset = &mut create_available_asset();
+		increase_usd_debt(available_asset, 100, 100_0000, 4).unwrap();
+		assert_eq!(available_asset.usd_debt, 10_000);
+		decrease_usd_debt(available_asset, 40, 100_0000, 4).unwrap();
+		assert_eq!(available_asset.usd_debt, 6_000);
+		decrease_usd_debt(available_asset, 100, 100_0000, 4).unwrap();
+		assert_eq!(available_asset.usd_debt, 0);
+	}
+
+	#[test]
+	fn reserves_within_caps() {
+		let available_asset = &mut create_available_asset();
+		available_asset.max_lptoken_amount = 400;
+		assert!(validate_max_reserves(available_asset).is_ok());
+		available_asset.pool_reserves = 401;
+		assert!(validate_max_reserves(available_asset).is_err());
+		available_asset.max_lptoken_amount = 0;
+		assert!(validate_max_reserves(available_asset).is_ok());
+
+		available_asset.usd_debt = 1_000;
+		available_asset.max_usd_debt = 999;
+		assert!(validate_max_reserves(available_asset).is_err());
+		available_asset.max_usd_debt = 1_000;
+		assert!(validate_max_reserves(available_asset).is_ok());
+	}
+
+	#[test]
+	fn stable_aum_is_pool_reserves() {
+		let available_asset = &mut create_available_asset();
+		available_asset.stable_token = true;
+		available_asset.occupied_reserves = 200;
+
+		let mut aum = AumAccumulator::default();
+		aum.add_asset(available_asset, 1_000_000, 4).unwrap();
+		assert_eq!(40_000, aum.aum());
+	}
+
+	#[test]
+	fn aum_counts_guaranteed_usd_of_longs() {
+		let available_asset = &mut create_available_asset();
+		available_asset.occupied_reserves = 200;
+		available_asset.net_protocol_liabilities = 10_000;
+
+		let mut aum = AumAccumulator::default();
+		aum.add_asset(available_asset, 1_000_000, 4).unwrap();
+		assert_eq!(30_000, aum.aum());
+	}
+
+	#[test]
+	fn aum_counts_global_short_pnl() {
+		let available_asset = &mut create_available_asset();
+		available_asset.global_short_size = 10;
+		available_asset.global_short_average_price = 80_000_000;
+
+		let mut aum = AumAccumulator::default();
+		aum.add_asset(available_asset, 1_000_000, 4).unwrap();
+		assert_eq!(40_200, aum.aum());
+
+		available_asset.global_short_average_price = 120_000_000;
+		let mut aum = AumAccumulator::default();
+		aum.add_asset(available_asset, 1_000_000, 4).unwrap();
+		assert_eq!(39_800, aum.aum());
+	}
+
+	#[test]
+	fn aum_values_reserves_with_their_token_decimals() {
+		// 50 USDC with 6 decimals at $1 and 2 SOL with 9 decimals at $25 are each worth $50
+		let usdc = AvailableAsset {
+			stable_token: true,
+			token_decimals: 6,
+			pool_reserves: 50_000_000,
+			..Default::default()
+		};
+		let sol = AvailableAsset {
+			token_decimals: 9,
+			pool_reserves: 2_000_000_000,
+			..Default::default()
+		};
+		assert_eq!(get_usd_value(&usdc, usdc.pool_reserves, 100_000_000, 8).unwrap(), 50 * PRICE_DECIMALS_FACTOR);
+		assert_eq!(get_usd_value(&sol, sol.pool_reserves, 2_500_000_000, 8).unwrap(), 50 * PRICE_DECIMALS_FACTOR);
+		assert_eq!(get_token_amount(&sol, 50 * PRICE_DECIMALS_FACTOR, 2_500_000_000, 8).unwrap(), 2_000_000_000);
+
+		let mut aum = AumAccumulator::default();
+		aum.add_asset(&usdc, 100_000_000, 8).unwrap();
+		aum.add_asset(&sol, 2_500_000_000, 8).unwrap();
+		assert_eq!(aum.aum(), 100 * PRICE_DECIMALS_FACTOR);
+	}
+
+	#[test]
+	fn first_mint_is_one_lp_token_per_usd() {
+		let exchange = Exchange::default();
+		let available_asset = &mut create_available_asset();
+		available_asset.max_lptoken_amount = 0;
+		// 1 token with 6 decimals at $100
+		let quote = apply_mint_lp(&exchange, available_asset, 0, 0, 100_0000, 4, 1_000_000).unwrap();
+		assert_eq!(quote.lp_amount, 100 * 10_u64.pow(LP_DECIMALS as u32));
+		assert_eq!(quote.fee_basis_points, 0);
+		assert_eq!(quote.pool_reserves, 1_000_400);
+		assert_eq!(available_asset.pool_reserves, 1_000_400);
+		assert_eq!(available_asset.usd_debt, 100 * PRICE_DECIMALS_FACTOR);
+	}
+
+	#[test]
+	fn mint_into_a_large_pool_does_not_overflow() {
+		let exchange = Exchange { total_weights: 5, ..Default::default() };
+		let available_asset = &mut create_available_asset();
+		available_asset.max_lptoken_amount = 0;
+		// 100M LP tokens backed by $100M, a deposit of $100 mints 100 LP tokens
+		let lp_supply = 100_000_000 * 10_u64.pow(LP_DECIMALS as u32);
+		let aum = 100_000_000 * PRICE_DECIMALS_FACTOR;
+		let quote = apply_mint_lp(&exchange, available_asset, lp_supply, aum, 100_0000, 4, 1_000_000).unwrap();
+		assert_eq!(quote.lp_amount, 100 * 10_u64.pow(LP_DECIMALS as u32));
+	}
+
+	#[test]
+	fn burn_fee_is_taken_on_the_redeemed_reserves() {
+		let exchange = Exchange { tax_basis_points: 8, mint_burn_basis_points: 15, total_weights: 20_000, ..Default::default() };
+		let available_asset = &mut AvailableAsset { token_decimals: 8, token_weight: 10_000, pool_reserves: 1998, ..Default::default() };
+		// 70_000 of 398_835 LP tokens redeem $0.0007 of the $0.003994 aum, about 700 of the 1998 reserves.
+		// Taxing the LP amount as reserves would empty the pool and charge 19 bps
+		let quote = apply_burn_lp(&exchange, available_asset, 398_835, 3994, 1_000_020, 4, 70_000).unwrap();
+		assert_eq!(quote.fee_basis_points, 16);
+		assert_eq!(quote.amount_out, 699);
+		assert_eq!(available_asset.pool_reserves, 1297);
+	}
+
+	#[test]
+	fn aum_only_counts_whitelisted_assets_of_the_exchange_once() {
+		let exchange_key = Pubkey::new_unique();
+		let available_asset = AvailableAsset { exchange: exchange_key, ..create_available_asset() };
+		let exchange = Exchange { assets: vec![available_asset.mint_address], ..Default::default() };
+		assert!(validate_aum_asset(&exchange_key, &exchange, &available_asset, &[]).is_ok());
+		// an asset of another exchange, an asset that is not whitelisted and an asset passed twice
+		assert!(validate_aum_asset(&Pubkey::new_unique(), &exchange, &available_asset, &[]).is_err());
+		assert!(validate_aum_asset(&exchange_key, &Exchange::default(), &available_asset, &[]).is_err());
+		assert!(validate_aum_asset(&exchange_key, &exchange, &available_asset, &[available_asset.mint_address]).is_err());
+	}
+}
@@ -5,10 +5,34 @@ pub mod init_lp_ata;
 pub mod mint_lp_token;
 pub mod burn_lp_token;
 pub mod swap;
+pub mod swap_exact_out;
 pub mod initialize_position;
 pub mod increase_position;
+pub mod decrease_position;
+pub mod close_position;
+pub mod liquidate_position;
+pub mod update_funding_rate;
+pub mod set_fees;
+pub mod update_available_asset;
+pub mod set_withdraw_only;
+pub mod remove_available_asset;
+pub mod propose_admin;
+pub mod accept_admin;
+pub mod set_roles;
+pub mod set_paused;
+pub mod set_asset_paused;
+pub mod set_treasury;
+pub mod withdraw_fees;
+pub mod quote_swap;
+pub mod quote_mint_lp;
+pub mod quote_burn_lp;
+pub mod get_lp_price;
 
 pub use increase_position::*;
+pub use decrease_position::*;
+pub use close_position::*;
+pub use liquidate_position::*;
+pub use update_funding_rate::*;
 pub use initialize_position::*;
 pub use swap::*;
 pub use initialize_exchange::*;
@@ -17,3 +41,15 @@ pub use initialize_available_asset::*;
 pub use init_lp_ata::*;
 pub use mint_lp_token::*;
 pub use burn_lp_token::*;
+pub use set_fees::*;
+pub use update_available_asset::*;
+pub use set_withdraw_only::*;
+pub use remove_available_asset::*;
+pub use propose_admin::*;
+pub use accept_admin::*;
+pub use set_roles::*;
+pub use set_paused::*;
+pub use set_asset_paused::*;
+pub use set_treasury::*;
+pub use withdraw_fees::*;
+pub use get_lp_price::*;
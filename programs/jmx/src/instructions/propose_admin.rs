@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+use crate::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String)]
+pub struct ProposeAdmin<'info> {
+	#[account(
+		constraint = exchange_admin.key() == exchange.admin @ ErrorCode::UnauthorizedAdmin
+	)]
+	pub exchange_admin: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+}
+
+// first step of an admin transfer, the current admin stays in control until
+// the proposed admin accepts, proposing the default pubkey cancels a transfer
+pub fn handler(ctx: Context<ProposeAdmin>, _exchange_name: String, new_admin: Pubkey) -> ProgramResult {
+	let exchange = &mut ctx.accounts.exchange;
+	exchange.pending_admin = new_admin;
+	verbose_msg!("proposed admin {}", exchange.pending_admin);
+	Ok(())
+}
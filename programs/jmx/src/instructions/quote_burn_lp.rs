@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use solana_program::program::set_return_data;
+use crate::*;
+use mint_lp_token::calculate_aum;
+use burn_lp_token::{apply_burn_lp, validate_burn_lp};
+
+// takes the accounts of burn_lp_token and returns the BurnLpQuote of the burn as
+// borsh return data, the burn is priced on a copy of the available asset so nothing is written
+pub fn handler(ctx: Context<BurnLpToken>, _exchange_name: String, _asset_name: String, lamports: u64) -> ProgramResult {
+	validate_burn_lp(
+		&ctx.accounts.exchange,
+		&ctx.accounts.available_asset,
+		ctx.remaining_accounts,
+		ctx.accounts.lp_mint.supply,
+		lamports
+	)?;
+	let (aum, _, oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
+		ctx.remaining_accounts, 
+		&ctx.accounts.exchange_reserve_token
+	)?;
+
+	let mut available_asset = (*ctx.accounts.available_asset).clone();
+	let quote = apply_burn_lp(
+		&ctx.accounts.exchange,
+		&mut available_asset,
+		ctx.accounts.lp_mint.supply,
+		aum,
+		oracle_price.max_price()?,
+		oracle_price.exponent,
+		lamports
+	)?;
+	set_return_data(&quote.try_to_vec()?);
+	Ok(())
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use solana_program::program::set_return_data;
+use crate::*;
+use mint_lp_token::{apply_mint_lp, calculate_aum, validate_mint_lp};
+
+// takes the accounts of mint_lp_token and returns the MintLpQuote of the mint as
+// borsh return data, the mint is priced on a copy of the available asset so nothing is written
+pub fn handler(ctx: Context<MintLpToken>, _exchange_name: String, _asset_name: String, lamports: u64) -> ProgramResult {
+	validate_mint_lp(&ctx.accounts.exchange, &ctx.accounts.available_asset, ctx.remaining_accounts, lamports)?;
+	let (_, aum, oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
+		ctx.remaining_accounts, 
+		&ctx.accounts.exchange_reserve_token
+	)?;
+
+	let mut available_asset = (*ctx.accounts.available_asset).clone();
+	let quote = apply_mint_lp(
+		&ctx.accounts.exchange,
+		&mut available_asset,
+		ctx.accounts.lp_mint.supply,
+		aum,
+		oracle_price.min_price()?,
+		oracle_price.exponent,
+		lamports
+	)?;
+	set_return_data(&quote.try_to_vec()?);
+	Ok(())
+}
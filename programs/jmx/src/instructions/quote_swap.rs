@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use solana_program::program::set_return_data;
+use crate::*;
+use mint_lp_token::calculate_aum;
+use swap::{apply_swap, validate_swap, SwapPrices};
+
+// takes the accounts of swap and returns the SwapQuote of the swap as borsh return
+// data, the swap is priced on copies of the available assets so nothing is written
+pub fn handler(
+	ctx: Context<Swap>,
+	_exchange_name: String,
+	_input_asset_name: String,
+	_output_asset_name: String,
+	lamports: u64
+) -> ProgramResult {
+	validate_swap(
+		&ctx.accounts.exchange,
+		&ctx.accounts.input_available_asset,
+		&ctx.accounts.output_available_asset,
+		ctx.remaining_accounts,
+		lamports
+	)?;
+	let (_, _, input_oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
+		ctx.remaining_accounts, 
+		&ctx.accounts.input_exchange_reserve_token
+	)?;
+	let (_, aum, output_oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
+		ctx.remaining_accounts, 
+		&ctx.accounts.output_exchange_reserve_token
+	)?;
+
+	let mut input_available_asset = (*ctx.accounts.input_available_asset).clone();
+	let mut output_available_asset = (*ctx.accounts.output_available_asset).clone();
+	let quote = apply_swap(
+		&ctx.accounts.exchange,
+		&mut input_available_asset,
+		&mut output_available_asset,
+		aum,
+		&SwapPrices {
+			input_precise_price: input_oracle_price.min_price()?,
+			input_exponent: input_oracle_price.exponent,
+			output_precise_price: output_oracle_price.max_price()?,
+			output_exponent: output_oracle_price.exponent,
+		},
+		lamports
+	)?;
+	set_return_data(&quote.try_to_vec()?);
+	Ok(())
+}
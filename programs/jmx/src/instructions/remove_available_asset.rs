@@ -0,0 +1,182 @@
+use anchor_spl::token::{Token, TokenAccount};
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String, asset_name: String)]
+pub struct RemoveAvailableAsset<'info> {
+	#[account(
+		mut,
+		constraint = asset_manager.key() == exchange.asset_manager @ ErrorCode::UnauthorizedRole
+	)]
+	pub asset_manager: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+	/// CHECK: this is our authority, no checked account required
+	#[account(
+		seeds = [EXCHANGE_AUTHORITY_SEED.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange_authority: UncheckedAccount<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
+		bump,
+		close = asset_manager,
+	)]
+	pub available_asset: Account<'info, AvailableAsset>,
+	#[account(
+		mut,
+		seeds = [asset_name.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange_reserve_token: Box<Account<'info, TokenAccount>>,
+	#[account(
+		mut,
+		constraint = asset_manager_token.owner == asset_manager.key() @ ErrorCode::InvalidTokenAccount,
+		constraint = asset_manager_token.mint == available_asset.mint_address @ ErrorCode::InvalidTokenAccount,
+	)]
+	pub asset_manager_token: Box<Account<'info, TokenAccount>>,
+	// Programs and Sysvars
+	pub token_program: Program<'info, Token>,
+}
+
+// second step of delisting an asset, once the withdraw only asset is drained
+// remove it from the exchange, drop its weight and return the rent of the
+// available asset and its reserve token account to the asset manager, any
+// rounding dust left in the reserve token account is swept to the asset manager
+pub fn handler(ctx: Context<RemoveAvailableAsset>, _exchange_name: String, _asset_name: String) -> ProgramResult {
+	let asset = &ctx.accounts.available_asset;
+	require!(asset.withdraw_only, ErrorCode::AssetNotWithdrawOnly);
+	validate_asset_drained(asset)?;
+
+	let mint_address = asset.mint_address;
+	let oracle_address = asset.oracle_address;
+	let token_weight = asset.token_weight;
+	let exchange = &mut ctx.accounts.exchange;
+	remove_asset_from_exchange(exchange, &mint_address, &oracle_address, token_weight)?;
+	verbose_msg!("removed asset {} total weights {}", mint_address, exchange.total_weights);
+
+	let exchange_auth_bump = match ctx.bumps.get("exchange_authority") {
+			Some(bump) => {
+					bump
+			}
+			None => {
+					msg!("Wrong bump key. Available keys are {:?}", ctx.bumps.keys());
+					return Err(ErrorCode::InvalidBump.into());
+			}
+	};
+
+	let exchange_name = ctx.accounts.exchange.name.as_ref();
+	let seeds = exchange_authority_seeds!(
+			exchange_name = exchange_name,
+			bump = *exchange_auth_bump
+	);
+	let signer = &[&seeds[..]];
+
+	let dust = ctx.accounts.exchange_reserve_token.amount;
+	if dust > 0 {
+		token::transfer(ctx.accounts.into_transfer_context(signer), dust)?;
+	}
+	token::close_account(ctx.accounts.into_close_account_context(signer))?;
+	Ok(())
+}
+
+/// An asset can only be removed once nothing is left in its reserves
+/// and no position is open against it, the token balance is not checked
+/// since rounding can leave dust in the reserve token account
+pub fn validate_asset_drained(asset: &AvailableAsset) -> Result<()> {
+	require!(
+		asset.pool_reserves == 0
+			&& asset.occupied_reserves == 0
+			&& asset.fee_reserves == 0
+			&& asset.global_short_size == 0
+			&& asset.net_protocol_liabilities == 0,
+		ErrorCode::AssetHasReserves
+	);
+	Ok(())
+}
+
+/// Drops the asset and its oracle from the exchange whitelists and its weight from the total weights
+pub fn remove_asset_from_exchange(exchange: &mut Exchange, mint_address: &Pubkey, oracle_address: &Pubkey, token_weight: u64) -> Result<()> {
+	exchange.assets.retain(|asset| asset != mint_address);
+	exchange.price_oracles.retain(|oracle| oracle != oracle_address);
+	exchange.total_weights = exchange.total_weights.checked_sub(token_weight).ok_or(ErrorCode::MathOverflow)?;
+	Ok(())
+}
+
+impl<'info> RemoveAvailableAsset<'info> {
+	pub fn into_transfer_context<'a, 'b, 'c>(
+		&self,
+		signer: &'a [&'b [&'c [u8]]]
+	) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+			let cpi_accounts = Transfer {
+					from: self.exchange_reserve_token.to_account_info(),
+					to: self.asset_manager_token.to_account_info(),
+					authority: self.exchange_authority.to_account_info(),
+			};
+			let cpi_program = self.token_program.to_account_info();
+			CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+	}
+
+	pub fn into_close_account_context<'a, 'b, 'c>(
+		&self,
+		signer: &'a [&'b [&'c [u8]]]
+	) -> CpiContext<'a, 'b, 'c, 'info, CloseAccount<'info>> {
+			let cpi_accounts = CloseAccount {
+					account: self.exchange_reserve_token.to_account_info(),
+					destination: self.asset_manager.to_account_info(),
+					authority: self.exchange_authority.to_account_info(),
+			};
+			let cpi_program = self.token_program.to_account_info();
+			CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn drained_asset_can_be_removed() {
+		let asset = AvailableAsset::default();
+		assert!(validate_asset_drained(&asset).is_ok());
+		let with_pool = AvailableAsset { pool_reserves: 1, ..Default::default() };
+		assert!(validate_asset_drained(&with_pool).is_err());
+	}
+
+	#[test]
+	fn removed_asset_drops_its_own_oracle() {
+		let usdc = Pubkey::new_unique();
+		let sol = Pubkey::new_unique();
+		let usdc_oracle = Pubkey::new_unique();
+		let sol_oracle = Pubkey::new_unique();
+		// oracles are not in the same order as the assets
+		let mut exchange = Exchange {
+			assets: vec![usdc, sol],
+			price_oracles: vec![sol_oracle, usdc_oracle],
+			total_weights: 15_000,
+			..Default::default()
+		};
+		remove_asset_from_exchange(&mut exchange, &sol, &sol_oracle, 5_000).unwrap();
+		assert_eq!(exchange.assets, vec![usdc]);
+		assert_eq!(exchange.price_oracles, vec![usdc_oracle]);
+		assert_eq!(exchange.total_weights, 10_000);
+	}
+
+	#[test]
+	fn asset_with_reserves_or_positions_cannot_be_removed() {
+		let with_fees = AvailableAsset { fee_reserves: 1, ..Default::default() };
+		assert!(validate_asset_drained(&with_fees).is_err());
+		let with_longs = AvailableAsset { occupied_reserves: 1, ..Default::default() };
+		assert!(validate_asset_drained(&with_longs).is_err());
+		let with_shorts = AvailableAsset { global_short_size: 1, ..Default::default() };
+		assert!(validate_asset_drained(&with_shorts).is_err());
+	}
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use crate::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String, asset_name: String)]
+pub struct SetAssetPaused<'info> {
+	#[account(
+		constraint = pauser.key() == exchange.pauser @ ErrorCode::UnauthorizedRole
+	)]
+	pub pauser: Signer<'info>,
+	#[account(
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
+		bump,
+	)]
+	pub available_asset: Account<'info, AvailableAsset>,
+}
+
+/// Which operations of an asset are paused, while the exchange wide pause stops everything
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct AssetPauseFlags {
+	pub mint_paused: bool,
+	pub burn_paused: bool,
+	pub swap_in_paused: bool,
+	pub swap_out_paused: bool,
+	pub increase_paused: bool,
+	pub decrease_paused: bool,
+}
+
+pub fn handler(ctx: Context<SetAssetPaused>, _exchange_name: String, _asset_name: String, flags: AssetPauseFlags) -> ProgramResult {
+	let asset = &mut ctx.accounts.available_asset;
+	asset.mint_paused = flags.mint_paused;
+	asset.burn_paused = flags.burn_paused;
+	asset.swap_in_paused = flags.swap_in_paused;
+	asset.swap_out_paused = flags.swap_out_paused;
+	asset.increase_paused = flags.increase_paused;
+	asset.decrease_paused = flags.decrease_paused;
+	verbose_msg!("asset {} paused {:?}", asset.mint_address, flags);
+	Ok(())
+}
@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::*;
+use crate::error::ErrorCode;
+use crate::events::FeesChanged;
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String)]
+pub struct SetFees<'info> {
+	#[account(
+		constraint = fee_manager.key() == exchange.fee_manager @ ErrorCode::UnauthorizedRole
+	)]
+	pub fee_manager: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+}
+
+/// Fees of the exchange, every basis point fee is capped at MAX_FEE_BASIS_POINTS
+/// and the liquidation fee at MAX_LIQUIDATION_FEE_USD
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct FeeParams {
+	pub tax_basis_points: u64,
+	pub stable_tax_basis_points: u64,
+	pub mint_burn_basis_points: u64,
+	pub swap_fee_basis_points: u64,
+	pub stable_swap_fee_basis_points: u64,
+	pub margin_fee_basis_points: u64,
+	pub liquidation_fee_usd: u64,
+	pub min_profit_time: u64,
+}
+
+// fees are read by the fee math on the next mint, burn, swap or position change
+pub fn handler(ctx: Context<SetFees>, _exchange_name: String, fees: FeeParams) -> ProgramResult {
+	validate_fees(&fees)?;
+
+	let exchange = &mut ctx.accounts.exchange;
+	exchange.tax_basis_points = fees.tax_basis_points;
+	exchange.stable_tax_basis_points = fees.stable_tax_basis_points;
+	exchange.mint_burn_basis_points = fees.mint_burn_basis_points;
+	exchange.swap_fee_basis_points = fees.swap_fee_basis_points;
+	exchange.stable_swap_fee_basis_points = fees.stable_swap_fee_basis_points;
+	exchange.margin_fee_basis_points = fees.margin_fee_basis_points;
+	exchange.liquidation_fee_usd = fees.liquidation_fee_usd;
+	exchange.min_profit_time = fees.min_profit_time;
+	emit!(FeesChanged {
+		tax_basis_points: fees.tax_basis_points,
+		stable_tax_basis_points: fees.stable_tax_basis_points,
+		mint_burn_basis_points: fees.mint_burn_basis_points,
+		swap_fee_basis_points: fees.swap_fee_basis_points,
+		stable_swap_fee_basis_points: fees.stable_swap_fee_basis_points,
+		margin_fee_basis_points: fees.margin_fee_basis_points,
+		liquidation_fee_usd: fees.liquidation_fee_usd,
+		min_profit_time: fees.min_profit_time,
+	});
+	Ok(())
+}
+
+pub fn validate_fees(fees: &FeeParams) -> Result<()> {
+	validate_fee_basis_points(fees.tax_basis_points)?;
+	validate_fee_basis_points(fees.stable_tax_basis_points)?;
+	validate_fee_basis_points(fees.mint_burn_basis_points)?;
+	validate_fee_basis_points(fees.swap_fee_basis_points)?;
+	validate_fee_basis_points(fees.stable_swap_fee_basis_points)?;
+	validate_fee_basis_points(fees.margin_fee_basis_points)?;
+	require!(fees.liquidation_fee_usd <= MAX_LIQUIDATION_FEE_USD, ErrorCode::InvalidFee);
+	Ok(())
+}
+
+pub fn validate_fee_basis_points(fee_basis_points: u64) -> Result<()> {
+	require!(fee_basis_points <= MAX_FEE_BASIS_POINTS, ErrorCode::InvalidFee);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fee_within_max_basis_points() {
+		assert!(validate_fee_basis_points(0).is_ok());
+		assert!(validate_fee_basis_points(MAX_FEE_BASIS_POINTS).is_ok());
+		assert!(validate_fee_basis_points(MAX_FEE_BASIS_POINTS + 1).is_err());
+	}
+
+	#[test]
+	fn fees_within_max() {
+		assert!(validate_fees(&FeeParams::default()).is_ok());
+		let margin_fee = FeeParams { margin_fee_basis_points: MAX_FEE_BASIS_POINTS + 1, ..Default::default() };
+		assert!(validate_fees(&margin_fee).is_err());
+		let liquidation_fee = FeeParams { liquidation_fee_usd: MAX_LIQUIDATION_FEE_USD + 1, ..Default::default() };
+		assert!(validate_fees(&liquidation_fee).is_err());
+	}
+}
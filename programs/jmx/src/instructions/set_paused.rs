@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+use crate::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String)]
+pub struct SetPaused<'info> {
+	#[account(
+		constraint = pauser.key() == exchange.pauser @ ErrorCode::UnauthorizedRole
+	)]
+	pub pauser: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+}
+
+// pauses every mint, burn, swap and position increase on the exchange,
+// decreasing, closing and liquidating positions are never paused
+pub fn handler(ctx: Context<SetPaused>, _exchange_name: String, paused: bool) -> ProgramResult {
+	let exchange = &mut ctx.accounts.exchange;
+	exchange.paused = paused;
+	verbose_msg!("exchange paused {}", exchange.paused);
+	Ok(())
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String)]
+pub struct SetRoles<'info> {
+	#[account(
+		constraint = exchange_admin.key() == exchange.admin @ ErrorCode::UnauthorizedAdmin
+	)]
+	pub exchange_admin: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+}
+
+pub fn handler(
+	ctx: Context<SetRoles>,
+	_exchange_name: String,
+	fee_manager: Pubkey,
+	asset_manager: Pubkey,
+	pauser: Pubkey,
+	keeper: Pubkey
+) -> ProgramResult {
+	let exchange = &mut ctx.accounts.exchange;
+	exchange.fee_manager = fee_manager;
+	exchange.asset_manager = asset_manager;
+	exchange.pauser = pauser;
+	exchange.keeper = keeper;
+	verbose_msg!("fee manager {} asset manager {} pauser {} keeper {}", fee_manager, asset_manager, pauser, keeper);
+	Ok(())
+}
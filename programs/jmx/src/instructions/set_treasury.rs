@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String)]
+pub struct SetTreasury<'info> {
+	#[account(
+		constraint = fee_manager.key() == exchange.fee_manager @ ErrorCode::UnauthorizedRole
+	)]
+	pub fee_manager: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+}
+
+pub fn handler(ctx: Context<SetTreasury>, _exchange_name: String, treasury: Pubkey, treasury_fee_basis_points: u64) -> ProgramResult {
+	require!(treasury_fee_basis_points <= BASIS_POINTS_PRECISION, ErrorCode::InvalidFee);
+	let exchange = &mut ctx.accounts.exchange;
+	exchange.treasury = treasury;
+	exchange.treasury_fee_basis_points = treasury_fee_basis_points;
+	verbose_msg!("treasury {} treasury fee basis points {}", treasury, treasury_fee_basis_points);
+	Ok(())
+}
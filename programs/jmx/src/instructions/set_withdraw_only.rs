@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+use crate::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String, asset_name: String)]
+pub struct SetWithdrawOnly<'info> {
+	#[account(
+		constraint = asset_manager.key() == exchange.asset_manager @ ErrorCode::UnauthorizedRole
+	)]
+	pub asset_manager: Signer<'info>,
+	#[account(
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
+		bump,
+	)]
+	pub available_asset: Account<'info, AvailableAsset>,
+}
+
+// first step of delisting an asset, a withdraw only asset takes no new mints,
+// swaps in or positions while lp burns, swaps out and position closes drain it
+pub fn handler(ctx: Context<SetWithdrawOnly>, _exchange_name: String, _asset_name: String, withdraw_only: bool) -> ProgramResult {
+	let asset = &mut ctx.accounts.available_asset;
+	asset.withdraw_only = withdraw_only;
+	verbose_msg!("asset {} withdraw only {}", asset.mint_address, asset.withdraw_only);
+	Ok(())
+}
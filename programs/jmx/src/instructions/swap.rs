@@ -3,9 +3,22 @@ use pyth_client::{PriceType};
 use anchor_lang::prelude::*;
 use crate::constants::*;
 use crate::*;
-use crate::error::{ErrorCode};
+use crate::error::ErrorCode;
 use std::cmp::max;
-use mint_lp_token::{calculate_aum, calculate_fee_basis_points};
+use mint_lp_token::{
+	calculate_aum,
+	calculate_fee_basis_points,
+	get_token_decimals,
+	get_usd_value,
+	validate_max_reserves,
+	ReserveChange
+};
+use increase_position::normalize_price;
+use crate::events::Swapped;
+use jmx_math::{mul_div, Price, Rounding, TokenAmount};
+
+/// fee estimates apply_swap_exact_out tries before charging the highest possible swap fee
+const MAX_SWAP_FEE_ESTIMATES: usize = 4;
 
 // need to check that the mint provided matches the redeemable mint
 // CHECK: that mints and provided assets match for all provided accounts
@@ -72,87 +85,51 @@ pub fn handler(
 	exchange_name: String, 
 	input_asset_name: String, 
 	output_asset_name: String, 
-	lamports: u64
+	lamports: u64,
+	min_out: u64
 ) -> ProgramResult {
-	// transfer asset in
-	// get the value of transferred asset in
-	// remove the bps of value from trading fees
-	// get the value in usd of the transferred assets
-	// divide the value by the price of the out asset
-	// determine the slippage/price impact
-		// get the worse base fee rate between using the two assets
-		// use the worse fee from the two calls to calculating basis fees as the fee
-		// uint256 feesBasisPoints0 = getFeeBasisPoints(_tokenIn, _usdgAmount, baseBps, taxBps, true);
-		// uint256 feesBasisPoints1 = getFeeBasisPoints(_tokenOut, _usdgAmount, baseBps, taxBps, false);
-	assert!(
-		ctx.remaining_accounts.len() / 2 == ctx.accounts.exchange.assets.len(), 
-		"must supply all whitelisted assets when minting"
-	);
-	assert!(lamports > 100, "too few lamports for transaction");
-
-	// transfer lamports from user to reserve_asset_token_acount
-	let input_exchange_reserve_token = &ctx.accounts.input_exchange_reserve_token;
-	let output_exchange_reserve_token = &ctx.accounts.output_exchange_reserve_token;
-	// msg!("lamports {}", lamports);
+	validate_swap(
+		&ctx.accounts.exchange,
+		&ctx.accounts.input_available_asset,
+		&ctx.accounts.output_available_asset,
+		ctx.remaining_accounts,
+		lamports
+	)?;
 
-	let (aum, input_precise_price, input_exponent) = calculate_aum(
+	let (_, _, input_oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
 		ctx.remaining_accounts, 
-		input_exchange_reserve_token,
-		&ctx.accounts.exchange.price_oracles
-	).unwrap();
+		&ctx.accounts.input_exchange_reserve_token
+	)?;
 
-	let (aum, output_precise_price, output_exponent) = calculate_aum(
+	let (_, aum, output_oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
 		ctx.remaining_accounts, 
-		output_exchange_reserve_token,
-		&ctx.accounts.exchange.price_oracles
-	).unwrap();
-
-	msg!("output_exponent {} input precise price {} lamports {}",output_exponent, input_precise_price, lamports );
-	let gross_output_asset_out = (input_precise_price as u128).
-		checked_mul(lamports as u128).
-		unwrap().
-		checked_mul(10_u128.pow(output_exponent as u32)).
-		unwrap().
-		checked_div(output_precise_price  as u128).
-		unwrap().
-		checked_div(10_u128.pow(input_exponent as u32)).
-		unwrap() as u64;
-
-	msg!("gross_output_asset_out {}", gross_output_asset_out);
-	let input_total_fee_in_basis_points = calculate_fee_basis_points(
-		aum,
-		&ctx.accounts.input_available_asset,
-		ctx.accounts.exchange.total_weights,
-		input_precise_price,
-		input_exponent,
-		lamports,
-		true
-	);
+		&ctx.accounts.output_exchange_reserve_token
+	)?;
 
-	let output_total_fee_in_basis_points = calculate_fee_basis_points(
+	// the input is valued at its min price and the output at its max price, in favor of the pool
+	let quote = apply_swap(
+		&ctx.accounts.exchange,
+		&mut ctx.accounts.input_available_asset,
+		&mut ctx.accounts.output_available_asset,
 		aum,
-		&ctx.accounts.output_available_asset,
-		ctx.accounts.exchange.total_weights,
-		output_precise_price,
-		output_exponent,
-		gross_output_asset_out,
-		false
-	);
-
-	let net_output_asset_out = gross_output_asset_out.
-		checked_mul(BASIS_POINTS_PRECISION as u64).
-		unwrap().
-		checked_div(max(input_total_fee_in_basis_points, output_total_fee_in_basis_points)).
-		unwrap();
-
-	msg!("net_output_asset_out {}", net_output_asset_out);
+		&SwapPrices {
+			input_precise_price: input_oracle_price.min_price()?,
+			input_exponent: input_oracle_price.exponent,
+			output_precise_price: output_oracle_price.max_price()?,
+			output_exponent: output_oracle_price.exponent,
+		},
+		lamports
+	)?;
+	require!(quote.amount_out >= min_out, ErrorCode::SlippageExceeded);
 	let exchange_auth_bump = match ctx.bumps.get("exchange_authority") {
 			Some(bump) => {
 					bump
 			}
 			None => {
 					msg!("Wrong bump key. Available keys are {:?}", ctx.bumps.keys());
-					panic!("Wrong bump key")
+					return Err(ErrorCode::InvalidBump.into());
 			}
 	};
 
@@ -170,20 +147,308 @@ pub fn handler(
 
 	token::transfer(
 		ctx.accounts.into_transfer_out_context(signer), 
-		net_output_asset_out as u64
+		quote.amount_out
 	)?;
 
-	let output_available_asset = &mut ctx.accounts.output_available_asset;
-	let input_available_asset = &mut ctx.accounts.input_available_asset;
-	assert!(
-		(output_available_asset.pool_reserves - output_available_asset.fee_reserves) > net_output_asset_out,
-		"not enough available pool reserves"
+	emit!(Swapped {
+		user: ctx.accounts.user_authority.key(),
+		input_mint: ctx.accounts.input_available_asset.mint_address,
+		output_mint: ctx.accounts.output_available_asset.mint_address,
+		amount_in: quote.amount_in,
+		amount_out: quote.amount_out,
+		input_price: quote.input_price,
+		input_exponent: quote.input_exponent,
+		output_price: quote.output_price,
+		output_exponent: quote.output_exponent,
+		fee_basis_points: quote.fee_basis_points,
+		input_pool_reserves: quote.input_pool_reserves,
+		output_pool_reserves: quote.output_pool_reserves,
+		output_fee_reserves: quote.output_fee_reserves,
+	});
+	Ok(())
+}
+
+/// Result of a swap, returned as return data by quote_swap. Prices are oracle prices in units of
+/// 10^-exponent USD and the reserves are the ones after the swap
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct SwapQuote {
+	pub amount_in: u64,
+	pub amount_out: u64,
+	pub fee_basis_points: u64,
+	pub input_price: u64,
+	pub input_exponent: u64,
+	pub output_price: u64,
+	pub output_exponent: u64,
+	pub input_pool_reserves: u64,
+	pub output_pool_reserves: u64,
+	pub output_fee_reserves: u64,
+}
+
+/// Oracle prices a swap is priced at, in units of 10^-exponent USD. The input is valued at its
+/// min price and the output at its max price
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SwapPrices {
+	pub input_precise_price: u64,
+	pub input_exponent: u64,
+	pub output_precise_price: u64,
+	pub output_exponent: u64,
+}
+
+pub fn validate_swap(
+	exchange: &Exchange,
+	input_available_asset: &AvailableAsset,
+	output_available_asset: &AvailableAsset,
+	remaining_accounts: &[AccountInfo],
+	lamports: u64
+) -> Result<()> {
+	// the same account passed as both sides would have the output write overwrite the input write on exit
+	require!(input_available_asset.mint_address != output_available_asset.mint_address, ErrorCode::SameSwapAsset);
+	require!(
+		exchange.assets.contains(&input_available_asset.mint_address)
+			&& exchange.assets.contains(&output_available_asset.mint_address),
+		ErrorCode::AssetNotWhitelisted
+	);
+	require!(!exchange.paused, ErrorCode::ExchangePaused);
+	require!(
+		!input_available_asset.swap_in_paused && !output_available_asset.swap_out_paused,
+		ErrorCode::ActionPaused
 	);
+	// a withdraw only asset can still be swapped out of the pool
+	require!(!input_available_asset.withdraw_only, ErrorCode::AssetWithdrawOnly);
+	require!(
+		remaining_accounts.len() / 4 == exchange.assets.len(), 
+		ErrorCode::MissingWhitelistedAssets
+	);
+	require!(lamports > 100, ErrorCode::AmountTooSmall);
+	Ok(())
+}
 
-	msg!("lamports in {} asset out {} fees kept in addition to asset out {}", lamports, net_output_asset_out, gross_output_asset_out - net_output_asset_out);
-	input_available_asset.pool_reserves += lamports;
-	output_available_asset.pool_reserves -= gross_output_asset_out;
-	output_available_asset.fee_reserves += gross_output_asset_out - net_output_asset_out;
+/// Prices a swap of lamports of the input asset at its precise price into the output asset at its precise price.
+/// The input joins the pool reserves, the gross output leaves them and the fee on it is kept in the fee
+/// reserves of the output asset.
+/// swap applies it to the accounts, quote_swap to copies of them
+pub fn apply_swap(
+	exchange: &Exchange,
+	input_available_asset: &mut AvailableAsset,
+	output_available_asset: &mut AvailableAsset,
+	aum: u64,
+	prices: &SwapPrices,
+	lamports: u64
+) -> Result<SwapQuote> {
+	let SwapPrices { input_precise_price, input_exponent, output_precise_price, output_exponent } = *prices;
+	// transfer asset in
+	// get the value of transferred asset in
+	// remove the bps of value from trading fees
+	// determine the slippage/price impact
+		// get the worse base fee rate between using the two assets
+		// use the worse fee from the two calls to calculating basis fees as the fee
+		// uint256 feesBasisPoints0 = getFeeBasisPoints(_tokenIn, _usdgAmount, baseBps, taxBps, true);
+		// uint256 feesBasisPoints1 = getFeeBasisPoints(_tokenOut, _usdgAmount, baseBps, taxBps, false);
+	verbose_msg!("output_exponent {} input precise price {} lamports {}",output_exponent, input_precise_price, lamports );
+	// converted at both prices and both mint decimals in a single division
+	let gross_output_asset_out = TokenAmount(lamports)
+		.convert(
+			Price(normalize_price(input_precise_price, input_exponent)?),
+			get_token_decimals(input_available_asset)?,
+			Price(normalize_price(output_precise_price, output_exponent)?),
+			get_token_decimals(output_available_asset)?,
+			Rounding::Down
+		)
+		.map_err(ErrorCode::from)?
+		.0;
+
+	verbose_msg!("gross_output_asset_out {}", gross_output_asset_out);
+	let total_fee_in_basis_points = calculate_swap_fee_basis_points(
+		exchange,
+		input_available_asset,
+		output_available_asset,
+		aum,
+		prices,
+		lamports,
+		gross_output_asset_out
+	)?;
+	let net_output_asset_out = mul_div(gross_output_asset_out, BASIS_POINTS_PRECISION, total_fee_in_basis_points, Rounding::Down)
+		.map_err(ErrorCode::from)?;
+
+	verbose_msg!("net_output_asset_out {}", net_output_asset_out);
+	settle_swap(
+		input_available_asset,
+		output_available_asset,
+		input_precise_price,
+		input_exponent,
+		lamports,
+		gross_output_asset_out,
+		net_output_asset_out
+	)?;
+
+	Ok(SwapQuote {
+		amount_in: lamports,
+		amount_out: net_output_asset_out,
+		fee_basis_points: total_fee_in_basis_points - BASIS_POINTS_PRECISION,
+		input_price: input_precise_price,
+		input_exponent,
+		output_price: output_precise_price,
+		output_exponent,
+		input_pool_reserves: input_available_asset.pool_reserves,
+		output_pool_reserves: output_available_asset.pool_reserves,
+		output_fee_reserves: output_available_asset.fee_reserves,
+	})
+}
+
+/// Inverse of apply_swap, prices the input needed for exactly amount_out of the output asset.
+/// The fee depends on the amounts it is charged on, so it is estimated from the amounts of the
+/// previous estimate until the fee of the amounts is covered by the fee charged. The gross output
+/// and the input are rounded up, in favor of the pool.
+/// swap_exact_out applies it to the accounts
+pub fn apply_swap_exact_out(
+	exchange: &Exchange,
+	input_available_asset: &mut AvailableAsset,
+	output_available_asset: &mut AvailableAsset,
+	aum: u64,
+	prices: &SwapPrices,
+	amount_out: u64
+) -> Result<SwapQuote> {
+	let SwapPrices { input_precise_price, input_exponent, output_precise_price, output_exponent } = *prices;
+	let input_price = Price(normalize_price(input_precise_price, input_exponent)?);
+	let output_price = Price(normalize_price(output_precise_price, output_exponent)?);
+	let input_token_decimals = get_token_decimals(input_available_asset)?;
+	let output_token_decimals = get_token_decimals(output_available_asset)?;
+	let amounts_for_fee = |total_fee_in_basis_points: u64| -> Result<(u64, u64)> {
+		let gross_output_asset_out = mul_div(amount_out, total_fee_in_basis_points, BASIS_POINTS_PRECISION, Rounding::Up)
+			.map_err(ErrorCode::from)?;
+		let lamports = TokenAmount(gross_output_asset_out)
+			.convert(output_price, output_token_decimals, input_price, input_token_decimals, Rounding::Up)
+			.map_err(ErrorCode::from)?
+			.0;
+		Ok((lamports, gross_output_asset_out))
+	};
+
+	let mut total_fee_in_basis_points = BASIS_POINTS_PRECISION;
+	let mut covered = false;
+	for _ in 0..MAX_SWAP_FEE_ESTIMATES {
+		let (lamports, gross_output_asset_out) = amounts_for_fee(total_fee_in_basis_points)?;
+		let fee_in_basis_points = calculate_swap_fee_basis_points(
+			exchange,
+			input_available_asset,
+			output_available_asset,
+			aum,
+			prices,
+			lamports,
+			gross_output_asset_out
+		)?;
+		verbose_msg!("estimated fee {} fee of the estimate {}", total_fee_in_basis_points, fee_in_basis_points);
+		if fee_in_basis_points <= total_fee_in_basis_points {
+			covered = true;
+			break;
+		}
+		total_fee_in_basis_points = fee_in_basis_points;
+	}
+	if !covered {
+		// no swap is charged more than the base fee and the full tax
+		let (swap_fee_basis_points, tax_basis_points) = get_swap_fee_basis_points(exchange, input_available_asset, output_available_asset);
+		total_fee_in_basis_points = BASIS_POINTS_PRECISION + swap_fee_basis_points + tax_basis_points;
+	}
+
+	let (lamports, gross_output_asset_out) = amounts_for_fee(total_fee_in_basis_points)?;
+	verbose_msg!("lamports in {} gross output {} fee {}", lamports, gross_output_asset_out, total_fee_in_basis_points);
+	settle_swap(
+		input_available_asset,
+		output_available_asset,
+		input_precise_price,
+		input_exponent,
+		lamports,
+		gross_output_asset_out,
+		amount_out
+	)?;
+
+	Ok(SwapQuote {
+		amount_in: lamports,
+		amount_out,
+		fee_basis_points: total_fee_in_basis_points - BASIS_POINTS_PRECISION,
+		input_price: input_precise_price,
+		input_exponent,
+		output_price: output_precise_price,
+		output_exponent,
+		input_pool_reserves: input_available_asset.pool_reserves,
+		output_pool_reserves: output_available_asset.pool_reserves,
+		output_fee_reserves: output_available_asset.fee_reserves,
+	})
+}
+
+/// The worse of the fees of the input joining the pool and of the gross output leaving it
+pub fn calculate_swap_fee_basis_points(
+	exchange: &Exchange,
+	input_available_asset: &AvailableAsset,
+	output_available_asset: &AvailableAsset,
+	aum: u64,
+	prices: &SwapPrices,
+	lamports: u64,
+	gross_output_asset_out: u64
+) -> Result<u64> {
+	let SwapPrices { input_precise_price, input_exponent, output_precise_price, output_exponent } = *prices;
+	let (swap_fee_basis_points, tax_basis_points) = get_swap_fee_basis_points(
+		exchange,
+		input_available_asset,
+		output_available_asset
+	);
+	verbose_msg!("swap_fee_basis_points {} tax_basis_points {}", swap_fee_basis_points, tax_basis_points);
+	let input_total_fee_in_basis_points = calculate_fee_basis_points(
+		aum,
+		input_available_asset,
+		exchange.total_weights,
+		input_precise_price,
+		input_exponent,
+		&ReserveChange {
+			amount: lamports,
+			increment: true,
+			fee_basis_points: swap_fee_basis_points,
+			tax_basis_points,
+		}
+	)?;
+
+	let output_total_fee_in_basis_points = calculate_fee_basis_points(
+		aum,
+		output_available_asset,
+		exchange.total_weights,
+		output_precise_price,
+		output_exponent,
+		&ReserveChange {
+			amount: gross_output_asset_out,
+			increment: false,
+			fee_basis_points: swap_fee_basis_points,
+			tax_basis_points,
+		}
+	)?;
+
+	Ok(max(input_total_fee_in_basis_points, output_total_fee_in_basis_points))
+}
+
+/// Moves lamports of the input into the pool, the gross output out of it and the part of the
+/// gross output that is not paid out into the fee reserves of the output asset
+fn settle_swap(
+	input_available_asset: &mut AvailableAsset,
+	output_available_asset: &mut AvailableAsset,
+	input_precise_price: u64,
+	input_exponent: u64,
+	lamports: u64,
+	gross_output_asset_out: u64,
+	net_output_asset_out: u64
+) -> Result<()> {
+	verbose_msg!("lamports in {} asset out {} fees kept in addition to asset out {}", lamports, net_output_asset_out, gross_output_asset_out - net_output_asset_out);
+	input_available_asset.pool_reserves = input_available_asset.pool_reserves.checked_add(lamports).ok_or(ErrorCode::MathOverflow)?;
+	output_available_asset.pool_reserves = output_available_asset.pool_reserves
+		.checked_sub(gross_output_asset_out)
+		.ok_or(ErrorCode::InsufficientReserves)?;
+	// the reserves held for open positions can not be swapped out
+	require!(output_available_asset.occupied_reserves <= output_available_asset.pool_reserves, ErrorCode::InsufficientReserves);
+	output_available_asset.fee_reserves = output_available_asset.fee_reserves
+		.checked_add(gross_output_asset_out - net_output_asset_out)
+		.ok_or(ErrorCode::MathOverflow)?;
+	// the usd debt moves from the output asset to the input asset, valued with the decimals of the input
+	let usd_debt_delta = get_usd_value(input_available_asset, lamports, input_precise_price, input_exponent)?;
+	input_available_asset.usd_debt = input_available_asset.usd_debt.checked_add(usd_debt_delta).ok_or(ErrorCode::MathOverflow)?;
+	output_available_asset.usd_debt = output_available_asset.usd_debt.saturating_sub(usd_debt_delta);
+	validate_max_reserves(input_available_asset)?;
 	Ok(())
 }
 
@@ -209,4 +474,169 @@ impl<'info> Swap<'info> {
 		let cpi_program = self.token_program.to_account_info();
 		CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
 	}
-}
\ No newline at end of file
+}
+
+/// Swaps between two stable assets are charged the stable base fee and stable tax,
+/// every other swap is charged the swap fee and tax
+pub fn get_swap_fee_basis_points(
+	exchange: &Exchange,
+	input_available_asset: &AvailableAsset,
+	output_available_asset: &AvailableAsset
+) -> (u64, u64) {
+	if input_available_asset.stable_token && output_available_asset.stable_token {
+		(exchange.stable_swap_fee_basis_points, exchange.stable_tax_basis_points)
+	} else {
+		(exchange.swap_fee_basis_points, exchange.tax_basis_points)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// USDC at $1 in, SOL at $25 out
+	const PRICES: SwapPrices = SwapPrices {
+		input_precise_price: 100_000_000,
+		input_exponent: 8,
+		output_precise_price: 2_500_000_000,
+		output_exponent: 8,
+	};
+
+	fn create_exchange() -> Exchange {
+		Exchange {
+			tax_basis_points: 8,
+			stable_tax_basis_points: 4,
+			swap_fee_basis_points: 30,
+			stable_swap_fee_basis_points: 8,
+			..Default::default()
+		}
+	}
+
+	fn create_available_asset(stable_token: bool) -> AvailableAsset {
+		AvailableAsset {
+			stable_token,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn stable_to_stable_swap_uses_stable_fees() {
+		let fees = get_swap_fee_basis_points(
+			&create_exchange(),
+			&create_available_asset(true),
+			&create_available_asset(true)
+		);
+		assert_eq!(fees, (8, 4));
+	}
+
+	#[test]
+	fn swap_with_a_volatile_asset_uses_swap_fees() {
+		let exchange = create_exchange();
+		assert_eq!(get_swap_fee_basis_points(&exchange, &create_available_asset(true), &create_available_asset(false)), (30, 8));
+		assert_eq!(get_swap_fee_basis_points(&exchange, &create_available_asset(false), &create_available_asset(true)), (30, 8));
+		assert_eq!(get_swap_fee_basis_points(&exchange, &create_available_asset(false), &create_available_asset(false)), (30, 8));
+	}
+
+	#[test]
+	fn swap_converts_between_decimals_and_moves_reserves() {
+		let exchange = Exchange { total_weights: 20_000, ..Default::default() };
+		let mut usdc = AvailableAsset {
+			stable_token: true,
+			token_decimals: 6,
+			token_weight: 10_000,
+			..Default::default()
+		};
+		let mut sol = AvailableAsset {
+			token_decimals: 9,
+			token_weight: 10_000,
+			pool_reserves: 10_000_000_000,
+			usd_debt: 250_000_000,
+			..Default::default()
+		};
+		// with no swap fee or tax 50 USDC at $1 buys exactly 2 SOL at $25
+		let quote = apply_swap(&exchange, &mut usdc, &mut sol, 250_000_000, &PRICES, 50_000_000).unwrap();
+		assert_eq!(quote.amount_out, 2_000_000_000);
+		assert_eq!(quote.fee_basis_points, 0);
+		assert_eq!(quote.input_pool_reserves, 50_000_000);
+		assert_eq!(quote.output_pool_reserves, 8_000_000_000);
+		assert_eq!(usdc.usd_debt, 50_000_000);
+		assert_eq!(sol.usd_debt, 200_000_000);
+	}
+
+	#[test]
+	fn swap_can_not_take_the_reserves_of_open_positions() {
+		let exchange = Exchange { total_weights: 20_000, ..Default::default() };
+		let usdc = AvailableAsset { stable_token: true, token_decimals: 6, token_weight: 10_000, ..Default::default() };
+		let sol = AvailableAsset {
+			token_decimals: 9,
+			token_weight: 10_000,
+			pool_reserves: 2_000_000_000,
+			usd_debt: 50_000_000,
+			..Default::default()
+		};
+		// the whole pool can be swapped out while nothing is reserved
+		let quote = apply_swap(&exchange, &mut usdc.clone(), &mut sol.clone(), 50_000_000, &PRICES, 50_000_000).unwrap();
+		assert_eq!(quote.output_pool_reserves, 0);
+		let mut reserved_sol = AvailableAsset { occupied_reserves: 1, ..sol };
+		assert!(apply_swap(&exchange, &mut usdc.clone(), &mut reserved_sol, 50_000_000, &PRICES, 50_000_000).is_err());
+	}
+
+	#[test]
+	fn swap_can_not_use_the_same_asset_on_both_sides() {
+		let sol = AvailableAsset { mint_address: Pubkey::new_unique(), ..Default::default() };
+		let exchange = Exchange { assets: vec![sol.mint_address], ..Default::default() };
+		assert!(validate_swap(&exchange, &sol, &sol, &[], 1_000).is_err());
+	}
+
+	#[test]
+	fn swap_exact_out_inverts_the_conversion() {
+		let exchange = Exchange { total_weights: 20_000, ..Default::default() };
+		let mut usdc = AvailableAsset { stable_token: true, token_decimals: 6, token_weight: 10_000, ..Default::default() };
+		let mut sol = AvailableAsset {
+			token_decimals: 9,
+			token_weight: 10_000,
+			pool_reserves: 10_000_000_000,
+			usd_debt: 250_000_000,
+			..Default::default()
+		};
+		// with no swap fee or tax exactly 2 SOL at $25 costs exactly 50 USDC at $1
+		let quote = apply_swap_exact_out(&exchange, &mut usdc, &mut sol, 250_000_000, &PRICES, 2_000_000_000).unwrap();
+		assert_eq!(quote.amount_in, 50_000_000);
+		assert_eq!(quote.amount_out, 2_000_000_000);
+		assert_eq!(quote.output_pool_reserves, 8_000_000_000);
+		assert_eq!(quote.output_fee_reserves, 0);
+		// a single lamport out still costs a whole USDC unit
+		let quote = apply_swap_exact_out(&exchange, &mut usdc, &mut sol, 250_000_000, &PRICES, 1).unwrap();
+		assert_eq!(quote.amount_in, 1);
+	}
+
+	#[test]
+	fn swap_exact_out_charges_at_least_the_exact_in_fee() {
+		let exchange = Exchange { total_weights: 20_000, ..create_exchange() };
+		// the pool already holds more USDC than SOL so the swap moves both away from their weights
+		let usdc = AvailableAsset {
+			stable_token: true,
+			token_decimals: 6,
+			token_weight: 10_000,
+			pool_reserves: 500_000_000,
+			usd_debt: 500_000_000,
+			..Default::default()
+		};
+		let sol = AvailableAsset {
+			token_decimals: 9,
+			token_weight: 10_000,
+			pool_reserves: 10_000_000_000,
+			usd_debt: 250_000_000,
+			..Default::default()
+		};
+		let quote = apply_swap_exact_out(&exchange, &mut usdc.clone(), &mut sol.clone(), 750_000_000, &PRICES, 2_000_000_000).unwrap();
+		assert!(quote.fee_basis_points > 0);
+		assert_eq!(quote.amount_out, 2_000_000_000);
+		assert_eq!(quote.output_fee_reserves, 10_000_000_000 - 2_000_000_000 - quote.output_pool_reserves);
+		// swapping the quoted input in delivers at least the requested output, one unit less does not
+		let exact_in = apply_swap(&exchange, &mut usdc.clone(), &mut sol.clone(), 750_000_000, &PRICES, quote.amount_in).unwrap();
+		assert!(exact_in.amount_out >= 2_000_000_000);
+		let short = apply_swap(&exchange, &mut usdc.clone(), &mut sol.clone(), 750_000_000, &PRICES, quote.amount_in - 1).unwrap();
+		assert!(short.amount_out < 2_000_000_000);
+	}
+}
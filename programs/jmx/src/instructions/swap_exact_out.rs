@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::*;
+use crate::error::ErrorCode;
+use mint_lp_token::calculate_aum;
+use swap::{apply_swap_exact_out, validate_swap, SwapPrices};
+use crate::events::Swapped;
+
+// takes the accounts of swap and delivers exactly amount_out of the output asset,
+// the input needed for it is priced by inverting the swap and may not exceed max_in
+pub fn handler(
+	ctx: Context<Swap>,
+	_exchange_name: String,
+	_input_asset_name: String,
+	_output_asset_name: String,
+	amount_out: u64,
+	max_in: u64
+) -> ProgramResult {
+	validate_swap(
+		&ctx.accounts.exchange,
+		&ctx.accounts.input_available_asset,
+		&ctx.accounts.output_available_asset,
+		ctx.remaining_accounts,
+		amount_out
+	)?;
+
+	let (_, _, input_oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
+		ctx.remaining_accounts, 
+		&ctx.accounts.input_exchange_reserve_token
+	)?;
+
+	let (_, aum, output_oracle_price) = calculate_aum(
+		&ctx.accounts.exchange,
+		ctx.remaining_accounts, 
+		&ctx.accounts.output_exchange_reserve_token
+	)?;
+
+	// the input is valued at its min price and the output at its max price, in favor of the pool
+	let quote = apply_swap_exact_out(
+		&ctx.accounts.exchange,
+		&mut ctx.accounts.input_available_asset,
+		&mut ctx.accounts.output_available_asset,
+		aum,
+		&SwapPrices {
+			input_precise_price: input_oracle_price.min_price()?,
+			input_exponent: input_oracle_price.exponent,
+			output_precise_price: output_oracle_price.max_price()?,
+			output_exponent: output_oracle_price.exponent,
+		},
+		amount_out
+	)?;
+	require!(quote.amount_in <= max_in, ErrorCode::MaxInputExceeded);
+	let exchange_auth_bump = match ctx.bumps.get("exchange_authority") {
+			Some(bump) => {
+					bump
+			}
+			None => {
+					msg!("Wrong bump key. Available keys are {:?}", ctx.bumps.keys());
+					return Err(ErrorCode::InvalidBump.into());
+			}
+	};
+
+	let exchange_name = ctx.accounts.exchange.name.as_ref();
+	let seeds = exchange_authority_seeds!(
+			exchange_name = exchange_name,
+			bump = *exchange_auth_bump
+	);
+	let signer = &[&seeds[..]];
+
+	token::transfer(
+		ctx.accounts.into_transfer_in_context(), 
+		quote.amount_in
+	)?;
+
+	token::transfer(
+		ctx.accounts.into_transfer_out_context(signer), 
+		quote.amount_out
+	)?;
+
+	emit!(Swapped {
+		user: ctx.accounts.user_authority.key(),
+		input_mint: ctx.accounts.input_available_asset.mint_address,
+		output_mint: ctx.accounts.output_available_asset.mint_address,
+		amount_in: quote.amount_in,
+		amount_out: quote.amount_out,
+		input_price: quote.input_price,
+		input_exponent: quote.input_exponent,
+		output_price: quote.output_price,
+		output_exponent: quote.output_exponent,
+		fee_basis_points: quote.fee_basis_points,
+		input_pool_reserves: quote.input_pool_reserves,
+		output_pool_reserves: quote.output_pool_reserves,
+		output_fee_reserves: quote.output_fee_reserves,
+	});
+	Ok(())
+}
@@ -1,16 +1,15 @@
-use anchor_spl::token::{Token};
 use anchor_lang::prelude::*;
 use crate::*;
+use crate::error::ErrorCode;
 
 #[derive(Accounts)]
-#[instruction(exchange_name: String)]
+#[instruction(exchange_name: String, asset_name: String)]
 pub struct UpdateAssetWhitelist<'info> {
     // exchange Authority accounts
     #[account(
-			mut,
-			constraint = exchange_admin.key() == exchange.admin
+			constraint = asset_manager.key() == exchange.asset_manager @ ErrorCode::UnauthorizedRole
 		)]
-    pub exchange_admin: Signer<'info>,
+    pub asset_manager: Signer<'info>,
     // exchange Accounts
     #[account(
 				mut,
@@ -18,16 +17,47 @@ pub struct UpdateAssetWhitelist<'info> {
         bump,
     )]
     pub exchange: Box<Account<'info, Exchange>>,
-    // Programs and Sysvars
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
+    #[account(
+        seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
+        bump,
+    )]
+    pub available_asset: Account<'info, AvailableAsset>,
 }
 
-pub fn handler(ctx: Context<UpdateAssetWhitelist>, en: String, assets: Vec<Pubkey>, price_oracles: Vec<Pubkey>) -> ProgramResult {
+// adds an initialized available asset and its oracle to the exchange whitelists,
+// assets only leave the whitelists through remove_available_asset once drained
+pub fn handler(ctx: Context<UpdateAssetWhitelist>, _exchange_name: String, _asset_name: String) -> ProgramResult {
 	let exchange = &mut ctx.accounts.exchange;
-	exchange.assets = assets;
-  exchange.price_oracles = price_oracles;
-  msg!("price oracles {:?}", exchange.price_oracles);
+	whitelist_asset(exchange, &ctx.accounts.available_asset)?;
+	verbose_msg!("assets {:?} price oracles {:?}", exchange.assets, exchange.price_oracles);
+	Ok(())
+}
+
+/// Adds the mint of the asset to the whitelisted assets and its primary oracle to the
+/// whitelisted price oracles, an oracle shared with another asset is only added once
+pub fn whitelist_asset(exchange: &mut Exchange, available_asset: &AvailableAsset) -> Result<()> {
+	require!(!exchange.assets.contains(&available_asset.mint_address), ErrorCode::DuplicateAsset);
+	exchange.assets.push(available_asset.mint_address);
+	if !exchange.price_oracles.contains(&available_asset.oracle_address) {
+		exchange.price_oracles.push(available_asset.oracle_address);
+	}
 	Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn whitelisting_adds_the_asset_and_its_oracle_once() {
+		let oracle = Pubkey::new_unique();
+		let usdc = AvailableAsset { mint_address: Pubkey::new_unique(), oracle_address: oracle, ..Default::default() };
+		let usdt = AvailableAsset { mint_address: Pubkey::new_unique(), oracle_address: oracle, ..Default::default() };
+		let mut exchange = Exchange::default();
+		whitelist_asset(&mut exchange, &usdc).unwrap();
+		whitelist_asset(&mut exchange, &usdt).unwrap();
+		assert_eq!(exchange.assets, vec![usdc.mint_address, usdt.mint_address]);
+		assert_eq!(exchange.price_oracles, vec![oracle]);
+		assert!(whitelist_asset(&mut exchange, &usdc).is_err());
+	}
+}
@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use crate::*;
+use crate::error::ErrorCode;
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String, asset_name: String)]
+pub struct UpdateAvailableAsset<'info> {
+	#[account(
+		constraint = asset_manager.key() == exchange.asset_manager @ ErrorCode::UnauthorizedRole
+	)]
+	pub asset_manager: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
+		bump,
+	)]
+	pub available_asset: Account<'info, AvailableAsset>,
+}
+
+// only the configuration of the asset is updated, the funding rate, shorts,
+// guaranteed usd, usd debt and reserves are accounting and are left untouched
+pub fn handler(ctx: Context<UpdateAvailableAsset>, _exchange_name: String, _asset_name: String, asset_data: AvailableAsset) -> ProgramResult {
+	let asset = &mut ctx.accounts.available_asset;
+	require!(asset.mint_address == asset_data.mint_address, ErrorCode::InvalidAvailableAsset);
+	validate_token_kind_change(asset, &asset_data)?;
+	validate_price_config(&asset_data)?;
+
+	let exchange = &mut ctx.accounts.exchange;
+	// the primary oracle has to stay whitelisted for the asset to be priced
+	replace_price_oracle(&mut exchange.price_oracles, &asset.oracle_address, &asset_data.oracle_address);
+	exchange.total_weights = update_total_weights(
+		exchange.total_weights,
+		asset.token_weight,
+		asset_data.token_weight
+	)?;
+	verbose_msg!("token weight {} -> {} total weights {}", asset.token_weight, asset_data.token_weight, exchange.total_weights);
+
+	asset.token_weight = asset_data.token_weight;
+	asset.min_profit_basis_points = asset_data.min_profit_basis_points;
+	asset.max_lptoken_amount = asset_data.max_lptoken_amount;
+	asset.max_usd_debt = asset_data.max_usd_debt;
+	asset.stable_token = asset_data.stable_token;
+	asset.shortable_token = asset_data.shortable_token;
+	asset.oracle_address = asset_data.oracle_address;
+	asset.backup_oracle_address = asset_data.backup_oracle_address;
+	asset.max_price_age_slots = asset_data.max_price_age_slots;
+	asset.max_price_confidence_basis_points = asset_data.max_price_confidence_basis_points;
+	asset.spread_basis_points = asset_data.spread_basis_points;
+
+	Ok(())
+}
+
+/// Replaces the old weight of an asset with its new weight in the cached total weights
+pub fn update_total_weights(total_weights: u64, old_token_weight: u64, new_token_weight: u64) -> Result<u64> {
+	Ok(total_weights
+		.checked_sub(old_token_weight)
+		.ok_or(ErrorCode::MathOverflow)?
+		.checked_add(new_token_weight)
+		.ok_or(ErrorCode::MathOverflow)?)
+}
+
+/// A max price age or max confidence of 0 rejects every oracle price, leaving the asset unpriceable
+pub fn validate_price_config(asset_data: &AvailableAsset) -> Result<()> {
+	require!(
+		asset_data.max_price_age_slots > 0 && asset_data.max_price_confidence_basis_points > 0,
+		ErrorCode::InvalidOracle
+	);
+	Ok(())
+}
+
+/// Swaps the old oracle of an asset for its new oracle in the whitelisted price oracles
+pub fn replace_price_oracle(price_oracles: &mut [Pubkey], old_oracle: &Pubkey, new_oracle: &Pubkey) {
+	for oracle in price_oracles.iter_mut().filter(|oracle| *oracle == old_oracle) {
+		*oracle = *new_oracle;
+	}
+}
+
+/// The stable and shortable flags decide how shorts, reserves and usd debt are
+/// accounted, so they can only change once none of those are outstanding
+pub fn validate_token_kind_change(asset: &AvailableAsset, asset_data: &AvailableAsset) -> Result<()> {
+	if asset.stable_token == asset_data.stable_token && asset.shortable_token == asset_data.shortable_token {
+		return Ok(());
+	}
+	require!(
+		asset.global_short_size == 0 && asset.occupied_reserves == 0 && asset.usd_debt == 0,
+		ErrorCode::AssetInUse
+	);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn total_weights_replace_old_weight() {
+		assert_eq!(update_total_weights(20_000, 10_000, 5_000).unwrap(), 15_000);
+		assert_eq!(update_total_weights(20_000, 10_000, 30_000).unwrap(), 40_000);
+		assert!(update_total_weights(5_000, 10_000, 5_000).is_err());
+	}
+
+	#[test]
+	fn rotated_oracle_replaces_the_old_oracle() {
+		let usdc_oracle = Pubkey::new_unique();
+		let sol_oracle = Pubkey::new_unique();
+		let new_sol_oracle = Pubkey::new_unique();
+		let mut price_oracles = vec![usdc_oracle, sol_oracle];
+		replace_price_oracle(&mut price_oracles, &sol_oracle, &new_sol_oracle);
+		assert_eq!(price_oracles, vec![usdc_oracle, new_sol_oracle]);
+		replace_price_oracle(&mut price_oracles, &new_sol_oracle, &new_sol_oracle);
+		assert_eq!(price_oracles, vec![usdc_oracle, new_sol_oracle]);
+	}
+
+	#[test]
+	fn price_config_must_accept_some_prices() {
+		let asset = AvailableAsset { max_price_age_slots: 25, max_price_confidence_basis_points: 100, ..Default::default() };
+		assert!(validate_price_config(&asset).is_ok());
+		assert!(validate_price_config(&AvailableAsset { max_price_age_slots: 0, ..asset.clone() }).is_err());
+		assert!(validate_price_config(&AvailableAsset { max_price_confidence_basis_points: 0, ..asset }).is_err());
+	}
+
+	#[test]
+	fn token_kind_only_changes_when_asset_is_unused() {
+		let stable = AvailableAsset { stable_token: true, ..Default::default() };
+		let shortable = AvailableAsset { shortable_token: true, ..Default::default() };
+		assert!(validate_token_kind_change(&AvailableAsset::default(), &stable).is_ok());
+
+		let with_shorts = AvailableAsset { shortable_token: true, global_short_size: 1, ..Default::default() };
+		assert!(validate_token_kind_change(&with_shorts, &AvailableAsset::default()).is_err());
+		assert!(validate_token_kind_change(&with_shorts, &shortable).is_ok());
+		let with_longs = AvailableAsset { occupied_reserves: 1, ..Default::default() };
+		assert!(validate_token_kind_change(&with_longs, &stable).is_err());
+		let with_debt = AvailableAsset { usd_debt: 1, ..Default::default() };
+		assert!(validate_token_kind_change(&with_debt, &shortable).is_err());
+	}
+}
@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+use std::convert::TryFrom;
+use crate::constants::*;
+use crate::*;
+use crate::error::ErrorCode;
+use crate::events::FundingUpdated;
+use jmx_math::{mul_div, Price, Rounding, TokenAmount};
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String, asset_name: String)]
+pub struct UpdateFundingRate<'info> {
+	// exchange Accounts
+	#[account(
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
+		bump
+	)]
+	pub available_asset: Account<'info, AvailableAsset>,
+}
+
+// Crank that anyone can call to accrue funding on an asset without touching a position
+pub fn handler(ctx: Context<UpdateFundingRate>, _exchange_name: String, _asset_name: String) -> ProgramResult {
+	require!(
+		ctx.accounts.exchange.assets.contains(&ctx.accounts.available_asset.mint_address),
+		ErrorCode::AssetNotWhitelisted
+	);
+	let asset = &mut ctx.accounts.available_asset;
+	update_cumulative_funding_rate(asset, Clock::get()?.unix_timestamp)?;
+	emit!(FundingUpdated {
+		mint: asset.mint_address,
+		cumulative_funding_rate: asset.cumulative_funding_rate,
+		last_funding_time: asset.last_funding_time,
+	});
+	Ok(())
+}
+
+/// Accrues funding for every FUNDING_INTERVAL that has passed since the last update
+pub fn update_cumulative_funding_rate(asset: &mut AvailableAsset, now: i64) -> Result<()> {
+	let interval = FUNDING_INTERVAL as u64;
+	let now = u64::try_from(now).map_err(|_| ErrorCode::MathOverflow)?;
+	if asset.last_funding_time == 0 {
+		asset.last_funding_time = now / interval * interval;
+		return Ok(());
+	}
+	if asset.last_funding_time.checked_add(interval).ok_or(ErrorCode::MathOverflow)? > now {
+		return Ok(());
+	}
+
+	let funding_rate = get_next_funding_rate(
+		asset.last_funding_time,
+		now,
+		asset.occupied_reserves,
+		asset.pool_reserves
+	)?;
+	asset.cumulative_funding_rate = asset.cumulative_funding_rate
+		.checked_add(funding_rate)
+		.ok_or(ErrorCode::MathOverflow)?;
+	asset.last_funding_time = now / interval * interval;
+	Ok(())
+}
+
+/// Funding rate for the elapsed intervals, scaled by the utilization of the pool reserves
+pub fn get_next_funding_rate(last_funding_time: u64, now: u64, occupied_reserves: u64, pool_reserves: u64) -> Result<u64> {
+	if pool_reserves == 0 {
+		return Ok(0);
+	}
+	let intervals = now.checked_sub(last_funding_time).ok_or(ErrorCode::MathOverflow)? / FUNDING_INTERVAL as u64;
+	let funding_rate_factor = FUNDING_RATE_FACTOR.checked_mul(intervals).ok_or(ErrorCode::MathOverflow)?;
+	Ok(mul_div(occupied_reserves, funding_rate_factor, pool_reserves, Rounding::Down).map_err(ErrorCode::from)?)
+}
+
+/// Funding owed in USD by a position since its entry funding rate, the size is in the smallest
+/// unit of a mint with token_decimals
+pub fn get_funding_fee(size: u64, price: u64, token_decimals: u8, entry_funding_rate: u64, cumulative_funding_rate: u64) -> Result<u64> {
+	if size == 0 {
+		return Ok(0);
+	}
+	let size_usd = TokenAmount(size).to_usd(Price(price), token_decimals, Rounding::Down).map_err(ErrorCode::from)?;
+	let funding_rate_delta = cumulative_funding_rate.checked_sub(entry_funding_rate).ok_or(ErrorCode::MathOverflow)?;
+	Ok(mul_div(size_usd.0, funding_rate_delta, FUNDING_RATE_PRECISION, Rounding::Down).map_err(ErrorCode::from)?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn first_update_only_sets_the_funding_time() {
+		let mut asset = AvailableAsset::default();
+		update_cumulative_funding_rate(&mut asset, 28_800 * 3 + 100).unwrap();
+		assert_eq!(asset.last_funding_time, 28_800 * 3);
+		assert_eq!(asset.cumulative_funding_rate, 0);
+	}
+
+	#[test]
+	fn no_accrual_within_an_interval() {
+		let mut asset = AvailableAsset { last_funding_time: 28_800, occupied_reserves: 50, pool_reserves: 100, ..Default::default() };
+		update_cumulative_funding_rate(&mut asset, 28_800 * 2 - 1).unwrap();
+		assert_eq!(asset.cumulative_funding_rate, 0);
+		assert_eq!(asset.last_funding_time, 28_800);
+	}
+
+	#[test]
+	fn accrues_per_interval_by_utilization() {
+		let mut asset = AvailableAsset { last_funding_time: 28_800, occupied_reserves: 50, pool_reserves: 100, ..Default::default() };
+		update_cumulative_funding_rate(&mut asset, 28_800 * 4 + 5).unwrap();
+		assert_eq!(asset.cumulative_funding_rate, 150);
+		assert_eq!(asset.last_funding_time, 28_800 * 4);
+	}
+
+	#[test]
+	fn funding_rate_overflow_is_an_error() {
+		assert!(get_next_funding_rate(0, 28_800, u64::MAX, 1).is_err());
+		assert!(update_cumulative_funding_rate(&mut AvailableAsset::default(), -1).is_err());
+	}
+
+	#[test]
+	fn funding_fee_since_entry() {
+		assert_eq!(get_funding_fee(200, 100_000_000, 0, 50, 150).unwrap(), 2_000_000);
+		assert_eq!(get_funding_fee(200_000_000, 100_000_000, 6, 50, 150).unwrap(), 2_000_000);
+	}
+}
@@ -0,0 +1,128 @@
+use anchor_spl::token::{Token, TokenAccount};
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::*;
+use crate::error::ErrorCode;
+use crate::events::FeesWithdrawn;
+use jmx_math::{apply_basis_points, Rounding};
+
+#[derive(Accounts)]
+#[instruction(exchange_name: String, asset_name: String)]
+pub struct WithdrawFees<'info> {
+	#[account(
+		constraint = fee_manager.key() == exchange.fee_manager @ ErrorCode::UnauthorizedRole
+	)]
+	pub fee_manager: Signer<'info>,
+	#[account(
+		seeds = [exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange: Box<Account<'info, Exchange>>,
+	/// CHECK: this is our authority, no checked account required
+	#[account(
+		seeds = [EXCHANGE_AUTHORITY_SEED.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange_authority: UncheckedAccount<'info>,
+	#[account(
+		mut,
+		seeds = [exchange_name.as_bytes(), asset_name.as_bytes()],
+		bump,
+	)]
+	pub available_asset: Account<'info, AvailableAsset>,
+	#[account(
+		mut,
+		seeds = [asset_name.as_bytes(), exchange_name.as_bytes()],
+		bump,
+	)]
+	pub exchange_reserve_token: Box<Account<'info, TokenAccount>>,
+	#[account(
+		mut,
+		constraint = treasury_token.owner == exchange.treasury @ ErrorCode::InvalidTreasuryAccount,
+		constraint = treasury_token.mint == available_asset.mint_address @ ErrorCode::InvalidTreasuryAccount,
+	)]
+	pub treasury_token: Box<Account<'info, TokenAccount>>,
+	// Programs and Sysvars
+	pub token_program: Program<'info, Token>,
+}
+
+// sends the treasury share of the accrued fees of an asset to the treasury
+// and leaves the LP share in the pool reserves, raising the value of the lp token
+pub fn handler(ctx: Context<WithdrawFees>, _exchange_name: String, _asset_name: String) -> ProgramResult {
+	let (treasury_amount, lp_amount) = split_fees(
+		ctx.accounts.available_asset.fee_reserves,
+		ctx.accounts.exchange.treasury_fee_basis_points
+	)?;
+
+	let asset = &mut ctx.accounts.available_asset;
+	asset.pool_reserves = asset.pool_reserves.checked_add(lp_amount).ok_or(ErrorCode::MathOverflow)?;
+	asset.fee_reserves = 0;
+	emit!(FeesWithdrawn {
+		mint: asset.mint_address,
+		treasury_amount,
+		lp_amount,
+		pool_reserves: asset.pool_reserves,
+	});
+
+	let exchange_auth_bump = match ctx.bumps.get("exchange_authority") {
+			Some(bump) => {
+					bump
+			}
+			None => {
+					msg!("Wrong bump key. Available keys are {:?}", ctx.bumps.keys());
+					return Err(ErrorCode::InvalidBump.into());
+			}
+	};
+
+	let exchange_name = ctx.accounts.exchange.name.as_ref();
+	let seeds = exchange_authority_seeds!(
+			exchange_name = exchange_name,
+			bump = *exchange_auth_bump
+	);
+	let signer = &[&seeds[..]];
+
+	token::transfer(
+		ctx.accounts.into_transfer_context(signer),
+		treasury_amount,
+	)?;
+	Ok(())
+}
+
+/// Splits the fee reserves into the treasury share and the LP share, rounding in favor of the LPs
+pub fn split_fees(fee_reserves: u64, treasury_fee_basis_points: u64) -> Result<(u64, u64)> {
+	let treasury_amount = apply_basis_points(fee_reserves, treasury_fee_basis_points, Rounding::Down).map_err(ErrorCode::from)?;
+	let lp_amount = fee_reserves.checked_sub(treasury_amount).ok_or(ErrorCode::MathOverflow)?;
+	Ok((treasury_amount, lp_amount))
+}
+
+impl<'info> WithdrawFees<'info> {
+	pub fn into_transfer_context<'a, 'b, 'c>(
+		&self,
+		signer: &'a [&'b [&'c [u8]]]
+	) -> CpiContext<'a, 'b, 'c, 'info, Transfer<'info>> {
+			let cpi_accounts = Transfer {
+					from: self.exchange_reserve_token.to_account_info(),
+					to: self.treasury_token.to_account_info(),
+					authority: self.exchange_authority.to_account_info(),
+			};
+			let cpi_program = self.token_program.to_account_info();
+			CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fees_split_between_treasury_and_lps() {
+		assert_eq!(split_fees(1_000, 3_000).unwrap(), (300, 700));
+		assert_eq!(split_fees(1_000, BASIS_POINTS_PRECISION).unwrap(), (1_000, 0));
+		assert_eq!(split_fees(1_000, 0).unwrap(), (0, 1_000));
+	}
+
+	#[test]
+	fn fee_split_rounds_in_favor_of_lps() {
+		assert_eq!(split_fees(9, 3_000).unwrap(), (2, 7));
+	}
+}
@@ -3,6 +3,9 @@ use anchor_spl::token::{self, Burn, CloseAccount, Mint, MintTo, Token, TokenAcco
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
 pub mod constants;
+pub mod error;
+pub mod events;
+pub mod oracle;
 pub mod types;
 pub mod instructions;
 pub mod state;
@@ -20,8 +23,20 @@ pub mod jmx {
         instructions::initialize_exchange::handler(ctx, exchange_name)
     }
 
-    pub fn update_asset_whitelist(ctx: Context<UpdateAssetWhitelist>, en: String, assets: Vec<Pubkey>, price_oracles: Vec<Pubkey>) -> ProgramResult {
-        instructions::update_asset_whitelist::handler(ctx, en, assets, price_oracles)
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, exchange_name: String, new_admin: Pubkey) -> ProgramResult {
+        instructions::propose_admin::handler(ctx, exchange_name, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>, exchange_name: String) -> ProgramResult {
+        instructions::accept_admin::handler(ctx, exchange_name)
+    }
+
+    pub fn set_roles(ctx: Context<SetRoles>, exchange_name: String, fee_manager: Pubkey, asset_manager: Pubkey, pauser: Pubkey, keeper: Pubkey) -> ProgramResult {
+        instructions::set_roles::handler(ctx, exchange_name, fee_manager, asset_manager, pauser, keeper)
+    }
+
+    pub fn update_asset_whitelist(ctx: Context<UpdateAssetWhitelist>, exchange_name: String, asset_name: String) -> ProgramResult {
+        instructions::update_asset_whitelist::handler(ctx, exchange_name, asset_name)
     }
     
     // Should throw an error if someone tries to init an already initialized available asset account or an already init-ed token account for that asset
@@ -29,29 +44,108 @@ pub mod jmx {
         instructions::initialize_available_asset::handler(ctx, exchange_name, asset_name, asset_data)
     }
 
+    pub fn update_available_asset(ctx: Context<UpdateAvailableAsset>, exchange_name: String, asset_name: String, asset_data: AvailableAsset) -> ProgramResult {
+        instructions::update_available_asset::handler(ctx, exchange_name, asset_name, asset_data)
+    }
+
+    pub fn set_treasury(ctx: Context<SetTreasury>, exchange_name: String, treasury: Pubkey, treasury_fee_basis_points: u64) -> ProgramResult {
+        instructions::set_treasury::handler(ctx, exchange_name, treasury, treasury_fee_basis_points)
+    }
+
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, exchange_name: String, asset_name: String) -> ProgramResult {
+        instructions::withdraw_fees::handler(ctx, exchange_name, asset_name)
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, exchange_name: String, paused: bool) -> ProgramResult {
+        instructions::set_paused::handler(ctx, exchange_name, paused)
+    }
+
+    pub fn set_asset_paused(ctx: Context<SetAssetPaused>, exchange_name: String, asset_name: String, flags: AssetPauseFlags) -> ProgramResult {
+        instructions::set_asset_paused::handler(ctx, exchange_name, asset_name, flags)
+    }
+
+    pub fn set_withdraw_only(ctx: Context<SetWithdrawOnly>, exchange_name: String, asset_name: String, withdraw_only: bool) -> ProgramResult {
+        instructions::set_withdraw_only::handler(ctx, exchange_name, asset_name, withdraw_only)
+    }
+
+    pub fn remove_available_asset(ctx: Context<RemoveAvailableAsset>, exchange_name: String, asset_name: String) -> ProgramResult {
+        instructions::remove_available_asset::handler(ctx, exchange_name, asset_name)
+    }
+
     pub fn init_lp_ata(ctx: Context<InitializeLpAta>) -> ProgramResult {
         instructions::init_lp_ata::handler(ctx)
     }
 
-    pub fn mint_lp_token(ctx: Context<MintLpToken>, exchange_name: String, asset_name: String, lamports: u64) -> ProgramResult {
-        instructions::mint_lp_token::handler(ctx, exchange_name, asset_name, lamports)
+    pub fn mint_lp_token(ctx: Context<MintLpToken>, exchange_name: String, asset_name: String, lamports: u64, min_lp_out: u64) -> ProgramResult {
+        instructions::mint_lp_token::handler(ctx, exchange_name, asset_name, lamports, min_lp_out)
+    }
+
+    pub fn burn_lp_token(ctx: Context<BurnLpToken>, exchange_name: String, asset_name: String, lamports: u64, min_reserve_out: u64) -> ProgramResult {
+        instructions::burn_lp_token::handler(ctx, exchange_name, asset_name, lamports, min_reserve_out)
     }
 
-    pub fn burn_lp_token(ctx: Context<BurnLpToken>, exchange_name: String, asset_name: String, lamports: u64) -> ProgramResult {
-        instructions::burn_lp_token::handler(ctx, exchange_name, asset_name, lamports)
+    pub fn swap(ctx: Context<Swap>, exchange_name: String, ia: String, oa: String, lamports: u64, min_out: u64) -> ProgramResult {
+        instructions::swap::handler(ctx, exchange_name, ia, oa, lamports, min_out)
     }
 
-    pub fn swap(ctx: Context<Swap>, exchange_name: String, ia: String, oa: String, lamports: u64) -> ProgramResult {
-        instructions::swap::handler(ctx, exchange_name, ia, oa, lamports)
+    pub fn swap_exact_out(ctx: Context<Swap>, exchange_name: String, ia: String, oa: String, amount_out: u64, max_in: u64) -> ProgramResult {
+        instructions::swap_exact_out::handler(ctx, exchange_name, ia, oa, amount_out, max_in)
     }
 
-    pub fn initialize_position(ctx: Context<InitializePosition>, exchange_name: String, asset_name: String) -> ProgramResult {
-        instructions::initialize_position::handler(ctx, exchange_name, asset_name)
+    pub fn quote_mint_lp(ctx: Context<MintLpToken>, exchange_name: String, asset_name: String, lamports: u64) -> ProgramResult {
+        instructions::quote_mint_lp::handler(ctx, exchange_name, asset_name, lamports)
     }
 
-    pub fn increase_position(ctx: Context<IncreasePosition>, exchange_name: String, asset_name: String) -> ProgramResult {
-        instructions::increase_position::handler(ctx, exchange_name, asset_name)
+    pub fn quote_burn_lp(ctx: Context<BurnLpToken>, exchange_name: String, asset_name: String, lamports: u64) -> ProgramResult {
+        instructions::quote_burn_lp::handler(ctx, exchange_name, asset_name, lamports)
     }
+
+    pub fn quote_swap(ctx: Context<Swap>, exchange_name: String, ia: String, oa: String, lamports: u64) -> ProgramResult {
+        instructions::quote_swap::handler(ctx, exchange_name, ia, oa, lamports)
+    }
+
+    pub fn get_lp_price(ctx: Context<GetLpPrice>, exchange_name: String) -> ProgramResult {
+        instructions::get_lp_price::handler(ctx, exchange_name)
+    }
+
+    pub fn initialize_position(ctx: Context<InitializePosition>, exchange_name: String, asset_name: String, is_long: bool) -> ProgramResult {
+        instructions::initialize_position::handler(ctx, exchange_name, asset_name, is_long)
+    }
+
+    pub fn increase_position(ctx: Context<IncreasePosition>, exchange_name: String, asset_name: String, collateral_asset_name: String, is_long: bool, collateral_amount: u64, size_delta: u64) -> ProgramResult {
+        instructions::increase_position::handler(ctx, exchange_name, asset_name, collateral_asset_name, is_long, collateral_amount, size_delta)
+    }
+
+    pub fn decrease_position(ctx: Context<DecreasePosition>, exchange_name: String, asset_name: String, collateral_asset_name: String, is_long: bool, params: DecreasePositionParams) -> ProgramResult {
+        instructions::decrease_position::handler(ctx, exchange_name, asset_name, collateral_asset_name, is_long, params)
+    }
+
+    pub fn close_position(ctx: Context<ClosePosition>, exchange_name: String, asset_name: String, collateral_asset_name: String, is_long: bool, receiver: Pubkey) -> ProgramResult {
+        instructions::close_position::handler(ctx, exchange_name, asset_name, collateral_asset_name, is_long, receiver)
+    }
+
+    pub fn liquidate_position(ctx: Context<LiquidatePosition>, exchange_name: String, asset_name: String, collateral_asset_name: String, is_long: bool) -> ProgramResult {
+        instructions::liquidate_position::handler(ctx, exchange_name, asset_name, collateral_asset_name, is_long)
+    }
+
+    pub fn update_funding_rate(ctx: Context<UpdateFundingRate>, exchange_name: String, asset_name: String) -> ProgramResult {
+        instructions::update_funding_rate::handler(ctx, exchange_name, asset_name)
+    }
+
+    pub fn set_fees(ctx: Context<SetFees>, exchange_name: String, fees: FeeParams) -> ProgramResult {
+        instructions::set_fees::handler(ctx, exchange_name, fees)
+    }
+}
+
+/// msg! for intermediate values of the fee and pricing math, only logged when built
+/// with the verbose-logs feature. Results are reported through the events module
+#[macro_export]
+macro_rules! verbose_msg {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "verbose-logs") {
+            msg!($($arg)*);
+        }
+    };
 }
 
 #[macro_export]
@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+use pyth_client::{PriceStatus, PriceType};
+use crate::constants::*;
+use crate::error::ErrorCode;
+use crate::state::{AvailableAsset, Exchange};
+
+/// A validated oracle price, price and confidence are in units of 10^-exponent USD
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OraclePrice {
+	pub price: u64,
+	pub exponent: u64,
+	pub confidence: u64,
+	/// Spread of the asset applied on top of the confidence
+	pub spread_basis_points: u64,
+}
+
+impl OraclePrice {
+	/// Distance of the min and max price from the price, the confidence plus the spread
+	pub fn deviation(&self) -> Result<u64> {
+		let spread = (self.price as u128)
+			.checked_mul(self.spread_basis_points as u128)
+			.ok_or(ErrorCode::MathOverflow)?
+			.checked_div(BASIS_POINTS_PRECISION as u128)
+			.ok_or(ErrorCode::MathOverflow)? as u64;
+		Ok(self.confidence.checked_add(spread).ok_or(ErrorCode::MathOverflow)?)
+	}
+
+	/// Price used when the protocol buys the asset from a user
+	pub fn min_price(&self) -> Result<u64> {
+		Ok(self.price.saturating_sub(self.deviation()?))
+	}
+
+	/// Price used when the protocol sells the asset to a user
+	pub fn max_price(&self) -> Result<u64> {
+		Ok(self.price.checked_add(self.deviation()?).ok_or(ErrorCode::MathOverflow)?)
+	}
+}
+
+/// Prices an asset from the oracles set on its available asset account, the primary oracle also has
+/// to be one of the price oracles whitelisted on the exchange. Falls back to the backup oracle when
+/// the primary oracle price is stale, not trading or too wide
+pub fn get_asset_price(
+	exchange: &Exchange,
+	oracle_account_info: &AccountInfo,
+	backup_oracle_account_info: &AccountInfo,
+	available_asset: &AvailableAsset
+) -> Result<OraclePrice> {
+	require!(exchange.price_oracles.contains(oracle_account_info.key), ErrorCode::InvalidOracle);
+	require!(oracle_account_info.key() == available_asset.oracle_address, ErrorCode::InvalidOracle);
+	require!(backup_oracle_account_info.key() == available_asset.backup_oracle_address, ErrorCode::InvalidOracle);
+
+	match get_oracle_price(oracle_account_info, available_asset) {
+		Ok(oracle_price) => Ok(oracle_price),
+		Err(err) => {
+			msg!(
+				"oracle {} unusable for {}: {}, falling back to backup oracle {}",
+				available_asset.oracle_address,
+				available_asset.mint_address,
+				err,
+				available_asset.backup_oracle_address
+			);
+			get_oracle_price(backup_oracle_account_info, available_asset)
+		}
+	}
+}
+
+/// Reads the pyth price account and validates it against the limits set on the available asset.
+/// Rejects accounts not owned by the pyth program, prices that are not trading, older than
+/// max_price_age_slots, or with a confidence wider than max_price_confidence_basis_points of the price
+pub fn get_oracle_price(oracle_account_info: &AccountInfo, available_asset: &AvailableAsset) -> Result<OraclePrice> {
+	require!(oracle_account_info.owner == &PYTH_PROGRAM_ID, ErrorCode::InvalidOracle);
+	let pyth_price_data = &oracle_account_info.try_borrow_data()?;
+	require!(pyth_price_data.len() >= std::mem::size_of::<pyth_client::Price>(), ErrorCode::InvalidOracle);
+	let pyth_price = pyth_client::cast::<pyth_client::Price>(pyth_price_data);
+	require!(matches!(pyth_price.ptype, PriceType::Price), ErrorCode::InvalidOracle);
+	require!(matches!(pyth_price.agg.status, PriceStatus::Trading), ErrorCode::PriceNotTrading);
+	require!(pyth_price.agg.price > 0, ErrorCode::InvalidOracle);
+
+	let current_slot = Clock::get()?.slot;
+	validate_price_age(pyth_price.agg.pub_slot, current_slot, available_asset.max_price_age_slots)?;
+
+	let oracle_price = OraclePrice {
+		price: pyth_price.agg.price as u64,
+		exponent: get_exponent(pyth_price.expo)?,
+		confidence: pyth_price.agg.conf,
+		spread_basis_points: available_asset.spread_basis_points,
+	};
+	validate_confidence(&oracle_price, available_asset.max_price_confidence_basis_points)?;
+	Ok(oracle_price)
+}
+
+/// Prices are read as price * 10^-exponent, a positive pyth exponent would scale the price the wrong way
+pub fn get_exponent(expo: i32) -> Result<u64> {
+	require!(expo <= 0, ErrorCode::InvalidOracle);
+	Ok(expo.unsigned_abs() as u64)
+}
+
+pub fn validate_price_age(pub_slot: u64, current_slot: u64, max_price_age_slots: u64) -> Result<()> {
+	require!(
+		current_slot.saturating_sub(pub_slot) <= max_price_age_slots,
+		ErrorCode::StalePrice
+	);
+	Ok(())
+}
+
+pub fn validate_confidence(oracle_price: &OraclePrice, max_price_confidence_basis_points: u64) -> Result<()> {
+	let confidence_basis_points = (oracle_price.confidence as u128)
+		.checked_mul(BASIS_POINTS_PRECISION as u128)
+		.ok_or(ErrorCode::MathOverflow)?
+		.checked_div(oracle_price.price as u128)
+		.ok_or(ErrorCode::MathOverflow)?;
+	require!(
+		confidence_basis_points <= max_price_confidence_basis_points as u128,
+		ErrorCode::PriceConfidenceTooWide
+	);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn price_within_max_age() {
+		assert!(validate_price_age(90, 100, 10).is_ok());
+		assert!(validate_price_age(89, 100, 10).is_err());
+	}
+
+	#[test]
+	fn positive_exponent_is_rejected() {
+		assert_eq!(get_exponent(-8).unwrap(), 8);
+		assert_eq!(get_exponent(0).unwrap(), 0);
+		assert!(get_exponent(2).is_err());
+	}
+
+	#[test]
+	fn confidence_within_max_basis_points() {
+		let oracle_price = OraclePrice { price: 1_000_000, exponent: 6, confidence: 10_000, spread_basis_points: 0 };
+		assert!(validate_confidence(&oracle_price, 100).is_ok());
+		assert!(validate_confidence(&oracle_price, 99).is_err());
+	}
+
+	#[test]
+	fn confidence_interval() {
+		let oracle_price = OraclePrice { price: 1_000_000, exponent: 6, confidence: 20, spread_basis_points: 0 };
+		assert_eq!(oracle_price.min_price().unwrap(), 999_980);
+		assert_eq!(oracle_price.max_price().unwrap(), 1_000_020);
+	}
+
+	#[test]
+	fn spread_widens_confidence_interval() {
+		let oracle_price = OraclePrice { price: 1_000_000, exponent: 6, confidence: 20, spread_basis_points: 10 };
+		assert_eq!(oracle_price.min_price().unwrap(), 998_980);
+		assert_eq!(oracle_price.max_price().unwrap(), 1_001_020);
+	}
+}
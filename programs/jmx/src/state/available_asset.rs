@@ -8,18 +8,37 @@ use serde::{Serialize, Deserialize};
 pub struct AvailableAsset {
 		/// Mint address of the available asset
 	pub mint_address: Pubkey,
+	/// The exchange the asset is listed on
+	pub exchange: Pubkey,
 	/// the decimals for the token
 	pub token_decimals: u64,
 	/// The weight of this token in the LP 
 	pub token_weight: u64,
 	/// min about of profit a position needs to be in to take profit before time
 	pub min_profit_basis_points: u64,
-	/// maximum amount of this token that can be in the pool
+	/// maximum amount of this token that can be in the pool, 0 for no cap
 	pub max_lptoken_amount: u64,
+	/// maximum usd debt the pool can take on in this token, 0 for no cap
+	/// This value is normalized with PRICE_DECIMALS and is ALWAYS in USD
+	pub max_usd_debt: u64,
 	/// Flag for whether this is a stable token
 	pub stable_token: bool,
 	/// Flag for whether this asset is shortable
 	pub shortable_token: bool,
+	/// Flag for whether the asset is being delisted, it takes no new mints, swaps in or positions
+	pub withdraw_only: bool,
+	/// Flag for whether minting lp with the asset is paused
+	pub mint_paused: bool,
+	/// Flag for whether burning lp for the asset is paused
+	pub burn_paused: bool,
+	/// Flag for whether swapping the asset into the pool is paused
+	pub swap_in_paused: bool,
+	/// Flag for whether swapping the asset out of the pool is paused
+	pub swap_out_paused: bool,
+	/// Flag for whether opening or increasing positions on the asset is paused
+	pub increase_paused: bool,
+	/// Flag for whether partially decreasing positions on the asset is paused, closing is always allowed
+	pub decrease_paused: bool,
 	/// The cumulative funding rate for the asset
 	pub cumulative_funding_rate: u64,
 	/// Last time the funding rate was updated
@@ -28,9 +47,20 @@ pub struct AvailableAsset {
 	pub oracle_address: Pubkey,
 	/// Backup account with price oracle data on the asset
 	pub backup_oracle_address: Pubkey,
+	/// Max number of slots since the oracle price was published before it is considered stale
+	pub max_price_age_slots: u64,
+	/// Max width of the oracle confidence interval as basis points of the price
+	pub max_price_confidence_basis_points: u64,
+	/// Spread added to the oracle confidence when pricing the asset in basis points of the price
+	pub spread_basis_points: u64,
 	/// Global size of shorts denominated in kind
 	pub global_short_size: u64,
+	/// Size weighted average entry price of all shorts on the asset
+	/// This value is normalized with PRICE_DECIMALS and is ALWAYS in USD
+	pub global_short_average_price: u64,
 	/// Represents the total outstanding obligations of the protocol (position - size) for the asset
+	/// The USD guaranteed to longs, the sum of long sizes less their collateral at entry
+	/// This value is normalized with PRICE_DECIMALS and is ALWAYS in USD
 	pub net_protocol_liabilities: u64,
 	/// Assets that are reserved and having positions trading against them
 	pub occupied_reserves: u64,
@@ -39,5 +69,8 @@ pub struct AvailableAsset {
 	/// Represents the unoccupied + occupied amount of assets in the pool for trading 
 	/// does not include fee_reserves
 	pub pool_reserves: u64,
+	/// USD owed by the pool for the deposits and swaps in of this asset, like usdgAmounts on GMX
+	/// This value is normalized with PRICE_DECIMALS and is ALWAYS in USD
+	pub usd_debt: u64,
 
 }
\ No newline at end of file
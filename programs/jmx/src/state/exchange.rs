@@ -27,6 +27,23 @@ pub struct Exchange {
 	pub min_profit_time: u64,
 	/// cache the total weights of the assets	
 	pub total_weights: u64,
+	/// share of the withdrawn fees sent to the treasury, the rest is left to the LPs in the pool
+	pub treasury_fee_basis_points: u64,
 	/// account that can make changes to the exchange
 	pub admin: Pubkey,
+	/// account proposed as the next admin, becomes admin once it accepts
+	pub pending_admin: Pubkey,
+	/// account that can set the fees and the treasury of the exchange and withdraw the fees
+	pub fee_manager: Pubkey,
+	/// account that can whitelist, configure and delist assets
+	pub asset_manager: Pubkey,
+	/// account that can pause the exchange
+	pub pauser: Pubkey,
+	/// account that runs the exchange cranks
+	pub keeper: Pubkey,
+	/// account that owns the token accounts receiving the treasury share of the fees
+	pub treasury: Pubkey,
+	/// Flag for whether the exchange is paused, blocks mints, burns, swaps and position increases
+	/// positions can still be decreased, closed and liquidated
+	pub paused: bool,
 }
\ No newline at end of file
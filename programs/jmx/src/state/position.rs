@@ -2,19 +2,24 @@ use anchor_lang::prelude::*;
 
 #[account]
 #[derive(Default)]
-// PDA seeds owner, is_long, collateral_mint, delivery_asset
+// PDA seeds exchange_name, owner, available_asset, is_long
 pub struct Position {
 	/// The user account address that owns the position
 	pub owner: Pubkey,
+	/// Whether the position is a long or a short on the available asset
+	pub is_long: bool,
 	/// The address of the collateral that was use to open the position 
 	pub collateral_mint: Pubkey,
-	/// The size of the position in the tokens decimals
+	/// The size of the position in the smallest unit of the available asset's Mint
 	pub size: u64,
+	/// The USD value of the collateral backing the position net of fees
+	/// This value is normalized with PRICE_DECIMALS, the collateral Mint decimals are already applied
+	pub collateral: u64,
 	/// The average price paid to open
 	/// This value is normalized with PRICE_DECIMALS and is ALWAYS in USD
 	pub average_price: u64,
-	/// how much of the delivery asset is reserved
-	/// In the delivery asset's Mint decimals 
+	/// how much of the collateral asset is reserved
+	/// In the collateral asset's Mint decimals 
 	pub reserve_amount: u64,
 	/// Entry number that is compared to ever increasing number cumulative 
 	pub entry_funding_rate: u64,
@@ -20,6 +20,8 @@ pub mod pyth {
         price_oracle.agg.conf = conf;
         price_oracle.expo = expo;
         price_oracle.ptype = pc::PriceType::Price;
+        price_oracle.agg.status = pc::PriceStatus::Trading;
+        price_oracle.agg.pub_slot = Clock::get()?.slot;
         Ok(())
     }
 
@@ -27,6 +29,7 @@ pub mod pyth {
         let oracle = &ctx.accounts.price;
         let mut price_oracle = Price::load(&oracle).unwrap();
         price_oracle.agg.price = price as i64;
+        price_oracle.agg.pub_slot = Clock::get()?.slot;
         Ok(())
     }
 }
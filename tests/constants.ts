@@ -3,6 +3,8 @@ import BN from 'bn.js';
 
 export class AvailableAsset extends Struct {
   mintAddress: PublicKey;
+	/// The exchange the asset is listed on
+	exchange: PublicKey;
 	/// the decimals for the token
 	tokenDecimals: BN;
 	/// The weight of this token in the LP 
@@ -11,10 +13,21 @@ export class AvailableAsset extends Struct {
 	minProfitBasisPoints: BN;
 	/// maximum amount of this token that can be in the pool
 	maxLptokenAmount: BN;
+	/// maximum usd debt the pool can take on in this token, 0 for no cap
+	maxUsdDebt: BN;
 	/// Flag for whether this is a stable token
 	stableToken: boolean;
 	/// Flag for whether this asset is shortable
 	shortableToken: boolean;
+	/// Flag for whether the asset is being delisted, it takes no new mints, swaps in or positions
+	withdrawOnly: boolean;
+	/// Flags for whether each action on the asset is paused
+	mintPaused: boolean;
+	burnPaused: boolean;
+	swapInPaused: boolean;
+	swapOutPaused: boolean;
+	increasePaused: boolean;
+	decreasePaused: boolean;
 	/// The cumulative funding rate for the asset
 	cumulativeFundingRate: BN;
 	/// Last time the funding rate was updated
@@ -23,6 +36,12 @@ export class AvailableAsset extends Struct {
 	oracleAddress: PublicKey;
 	/// Backup account with price oracle data on the asset
 	backupOracleAddress: PublicKey;
+	/// Max number of slots since the oracle price was published before it is considered stale
+	maxPriceAgeSlots: BN;
+	/// Max width of the oracle confidence interval as basis points of the price
+	maxPriceConfidenceBasisPoints: BN;
+	/// Spread added to the oracle confidence when pricing the asset in basis points of the price
+	spreadBasisPoints: BN;
 	/// Global size of shorts denominated in kind
 	globalShortSize: BN;
 	/// Represents the total outstanding obligations of the protocol (position - size) for the asset
@@ -51,7 +51,6 @@ describe('jmx', () => {
   let lpTokenAta;
   let remainingAccounts;
   let numOfDeposits = 0
-  let fullPenaltyAndFeeMultiplier = .9940
   
   const exchangeAdmin = anchor.web3.Keypair.generate();
 
@@ -157,9 +156,200 @@ describe('jmx', () => {
     assert.equal(exchangeAccountData.minProfitTime.toNumber(), 15);
     assert.equal(exchangeAccountData.totalWeights.toNumber(), 0);
     assert.equal(exchangeAccountData.admin.toString(), exchangeAdmin.publicKey.toString());
+    assert.equal(exchangeAccountData.pendingAdmin.toString(), anchor.web3.PublicKey.default.toString());
+    assert.equal(exchangeAccountData.feeManager.toString(), exchangeAdmin.publicKey.toString());
+    assert.equal(exchangeAccountData.assetManager.toString(), exchangeAdmin.publicKey.toString());
+    assert.equal(exchangeAccountData.pauser.toString(), exchangeAdmin.publicKey.toString());
+    assert.equal(exchangeAccountData.keeper.toString(), exchangeAdmin.publicKey.toString());
+    assert.equal(exchangeAccountData.paused, false);
+    assert.equal(exchangeAccountData.treasury.toString(), exchangeAdmin.publicKey.toString());
+    assert.equal(exchangeAccountData.treasuryFeeBasisPoints.toNumber(), 10000);
     assert.equal((String.fromCharCode.apply(null, exchangeAccountData.name)) === 'jmx                 ', true);
   });
 
+  it('transfers the admin in two steps and back', async () => {
+    const newAdmin = anchor.web3.Keypair.generate();
+
+    await program.rpc.proposeAdmin(
+      exchangeName,
+      newAdmin.publicKey,
+      {
+        accounts: {
+          exchangeAdmin: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+
+    // only the proposed admin can accept
+    await assert.rejects(
+      program.rpc.acceptAdmin(
+        exchangeName,
+        {
+          accounts: {
+            pendingAdmin: exchangeAdmin.publicKey,
+            exchange: exchangePda,
+          },
+          signers: [
+            exchangeAdmin
+          ]
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Signer is not the pending exchange admin");
+        return true;
+      }
+    );
+
+    await program.rpc.acceptAdmin(
+      exchangeName,
+      {
+        accounts: {
+          pendingAdmin: newAdmin.publicKey,
+          exchange: exchangePda,
+        },
+        signers: [
+          newAdmin
+        ]
+      }
+    );
+
+    let exchangeAccount = await program.provider.connection.getAccountInfo(
+      exchangePda
+    );
+    let exchangeAccountData = program.coder.accounts.decode('Exchange', exchangeAccount.data)
+    assert.equal(exchangeAccountData.admin.toString(), newAdmin.publicKey.toString());
+    assert.equal(exchangeAccountData.pendingAdmin.toString(), anchor.web3.PublicKey.default.toString());
+    // roles are not handed over with the admin
+    assert.equal(exchangeAccountData.feeManager.toString(), exchangeAdmin.publicKey.toString());
+
+    await program.rpc.proposeAdmin(
+      exchangeName,
+      exchangeAdmin.publicKey,
+      {
+        accounts: {
+          exchangeAdmin: newAdmin.publicKey,
+          exchange: exchangePda,
+        },
+        signers: [
+          newAdmin
+        ]
+      }
+    );
+    await program.rpc.acceptAdmin(
+      exchangeName,
+      {
+        accounts: {
+          pendingAdmin: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+
+    exchangeAccount = await program.provider.connection.getAccountInfo(
+      exchangePda
+    );
+    exchangeAccountData = program.coder.accounts.decode('Exchange', exchangeAccount.data)
+    assert.equal(exchangeAccountData.admin.toString(), exchangeAdmin.publicKey.toString());
+  });
+
+  it('sets the exchange fees and rejects fees above the max', async () => {
+    await assert.rejects(
+      program.rpc.setFees(
+        exchangeName,
+        {
+          taxBasisPoints: new BN(501),
+          stableTaxBasisPoints: new BN(4),
+          mintBurnBasisPoints: new BN(15),
+          swapFeeBasisPoints: new BN(30),
+          stableSwapFeeBasisPoints: new BN(8),
+          marginFeeBasisPoints: new BN(1),
+          liquidationFeeUsd: new BN(40),
+          minProfitTime: new BN(15),
+        },
+        {
+          accounts: {
+            feeManager: exchangeAdmin.publicKey,
+            exchange: exchangePda,
+          },
+          signers: [
+            exchangeAdmin
+          ]
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Fee exceeds the max fee");
+        return true;
+      }
+    );
+
+    await program.rpc.setFees(
+      exchangeName,
+      {
+        taxBasisPoints: new BN(10),
+        stableTaxBasisPoints: new BN(5),
+        mintBurnBasisPoints: new BN(20),
+        swapFeeBasisPoints: new BN(35),
+        stableSwapFeeBasisPoints: new BN(9),
+        marginFeeBasisPoints: new BN(2),
+        liquidationFeeUsd: new BN(50),
+        minProfitTime: new BN(30),
+      },
+      {
+        accounts: {
+          feeManager: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+
+    let exchangeAccount = await program.provider.connection.getAccountInfo(
+      exchangePda
+    );
+    const exchangeAccountData = program.coder.accounts.decode('Exchange', exchangeAccount.data)
+    assert.equal(exchangeAccountData.taxBasisPoints.toNumber(), 10);
+    assert.equal(exchangeAccountData.stableTaxBasisPoints.toNumber(), 5);
+    assert.equal(exchangeAccountData.mintBurnBasisPoints.toNumber(), 20);
+    assert.equal(exchangeAccountData.swapFeeBasisPoints.toNumber(), 35);
+    assert.equal(exchangeAccountData.stableSwapFeeBasisPoints.toNumber(), 9);
+    assert.equal(exchangeAccountData.marginFeeBasisPoints.toNumber(), 2);
+    assert.equal(exchangeAccountData.liquidationFeeUsd.toNumber(), 50);
+    assert.equal(exchangeAccountData.minProfitTime.toNumber(), 30);
+
+    // restore the initial fees the rest of the suite expects
+    await program.rpc.setFees(
+      exchangeName,
+      {
+        taxBasisPoints: new BN(8),
+        stableTaxBasisPoints: new BN(4),
+        mintBurnBasisPoints: new BN(15),
+        swapFeeBasisPoints: new BN(30),
+        stableSwapFeeBasisPoints: new BN(8),
+        marginFeeBasisPoints: new BN(1),
+        liquidationFeeUsd: new BN(40),
+        minProfitTime: new BN(15),
+      },
+      {
+        accounts: {
+          feeManager: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+  });
+
   // Need to write test for adding multiple assets, 
   // removing some assets while adding some assets
   it('Updates asset whitelist and creates a new available asset for USDC', async () => {
@@ -179,17 +369,31 @@ describe('jmx', () => {
 
     const availableAssetInputData = new AvailableAsset({
       mintAddress: fakeUsdcMint,
-      tokenDecimals: new BN(1),
+      exchange: exchangePda,
+      tokenDecimals: new BN(8),
       tokenWeight: new BN(10000),
       minProfitBasisPoints: new BN(1),
-      maxLptokenAmount: new BN(1),
+      maxLptokenAmount: new BN(1000000),
+      maxUsdDebt: new BN(0),
       stableToken: true,
       shortableToken: true,
+      withdrawOnly: false,
+      mintPaused: false,
+      burnPaused: false,
+      swapInPaused: false,
+      swapOutPaused: false,
+      increasePaused: false,
+      decreasePaused: false,
       cumulativeFundingRate: new BN(0),
       lastFundingTime: new BN(0),
       oracleAddress: usdcOraclePubkey,
       backupOracleAddress: usdcOraclePubkey,
+      // the mock oracle is only published once when the suite starts
+      maxPriceAgeSlots: new BN(10000),
+      maxPriceConfidenceBasisPoints: new BN(100),
+      spreadBasisPoints: new BN(0),
       globalShortSize: new BN(0),
+      globalShortAveragePrice: new BN(0),
       netProtocolLiabilities: new BN(0),
     })
 
@@ -199,7 +403,7 @@ describe('jmx', () => {
       availableAssetInputData,
       {
         accounts: {
-          exchangeAdmin: exchangeAdmin.publicKey,
+          assetManager: exchangeAdmin.publicKey,
           exchangeAuthority: exchangeAuthorityPda,
           exchange: exchangePda,
           mint: fakeUsdcMint,
@@ -218,16 +422,12 @@ describe('jmx', () => {
 
    tx = await program.rpc.updateAssetWhitelist(
       exchangeName,
-      [fakeUsdcMint, fakeWSolMint],
-      [usdcOraclePubkey, wSolOraclePubkey],
+      usdcSeed,
       {
         accounts: {
-          exchangeAdmin: exchangeAdmin.publicKey,
+          assetManager: exchangeAdmin.publicKey,
           exchange: exchangePda,
-          //System stuff
-          systemProgram: anchor.web3.SystemProgram.programId,
-          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          availableAsset: availableAssetPdaUsdc,
         },
         signers: [
           exchangeAdmin
@@ -239,25 +439,31 @@ describe('jmx', () => {
       exchangePda
     );
     const exchangeAccountData = program.coder.accounts.decode('Exchange', exchangeAccount.data)
+    assert.equal(exchangeAccountData.assets.length, 1);
     assert.equal(exchangeAccountData.assets[0].toString(), fakeUsdcMint.toString());
-    assert.equal(exchangeAccountData.assets[1].toString(), fakeWSolMint.toString())
-
+    assert.equal(exchangeAccountData.priceOracles.length, 1);
     assert.equal(exchangeAccountData.priceOracles[0].toString(), usdcOraclePubkey.toString());
-    assert.equal(exchangeAccountData.priceOracles[1].toString(), wSolOraclePubkey.toString())
     let availableAssetAccount = await provider.connection.getAccountInfo(
       availableAssetPdaUsdc
     );
     const availableAssetAccountData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
-    assert.equal(availableAssetAccountData.tokenDecimals.toNumber(), 1);
+    assert.equal(availableAssetAccountData.tokenDecimals.toNumber(), 8);
     assert.equal(availableAssetAccountData.tokenWeight.toNumber(), 10000);
     assert.equal(availableAssetAccountData.minProfitBasisPoints.toNumber(), 1);
-    assert.equal(availableAssetAccountData.maxLptokenAmount.toNumber(), 1);
+    assert.equal(availableAssetAccountData.maxLptokenAmount.toNumber(), 1000000);
+    assert.equal(availableAssetAccountData.maxUsdDebt.toNumber(), 0);
     assert.equal(availableAssetAccountData.cumulativeFundingRate.toNumber(), 0);
-    assert.equal(availableAssetAccountData.lastFundingTime.toNumber(), 0);
+    assert.equal(availableAssetAccountData.lastFundingTime.toNumber() > 0, true);
     assert.equal(availableAssetAccountData.stableToken, true);
     assert.equal(availableAssetAccountData.shortableToken, true);
+    assert.equal(availableAssetAccountData.withdrawOnly, false);
+    assert.equal(availableAssetAccountData.mintPaused, false);
+    assert.equal(availableAssetAccountData.decreasePaused, false);
     assert.equal(availableAssetAccountData.oracleAddress.toString(), usdcOraclePubkey.toString());
     assert.equal(availableAssetAccountData.backupOracleAddress.toString(), usdcOraclePubkey.toString());
+    assert.equal(availableAssetAccountData.maxPriceAgeSlots.toNumber(), 10000);
+    assert.equal(availableAssetAccountData.maxPriceConfidenceBasisPoints.toNumber(), 100);
+    assert.equal(availableAssetAccountData.spreadBasisPoints.toNumber(), 0);
     assert.equal(availableAssetAccountData.globalShortSize.toNumber(), 0);
     assert.equal(availableAssetAccountData.netProtocolLiabilities.toNumber(), 0);
     assert.equal(availableAssetAccountData.mintAddress.toString(), fakeUsdcMint.toString());
@@ -287,17 +493,31 @@ describe('jmx', () => {
 
     const availableAssetInputData = new AvailableAsset({
       mintAddress: fakeWSolMint,
-      tokenDecimals: new BN(1),
+      exchange: exchangePda,
+      tokenDecimals: new BN(8),
       tokenWeight: new BN(10000),
       minProfitBasisPoints: new BN(1),
-      maxLptokenAmount: new BN(1),
-      stableToken: true,
+      maxLptokenAmount: new BN(100000),
+      maxUsdDebt: new BN(0),
+      stableToken: false,
       shortableToken: true,
+      withdrawOnly: false,
+      mintPaused: false,
+      burnPaused: false,
+      swapInPaused: false,
+      swapOutPaused: false,
+      increasePaused: false,
+      decreasePaused: false,
       cumulativeFundingRate: new BN(0),
       lastFundingTime: new BN(0),
       oracleAddress: wSolOraclePubkey,
       backupOracleAddress: wSolOraclePubkey,
+      // the mock oracle is only published once when the suite starts
+      maxPriceAgeSlots: new BN(10000),
+      maxPriceConfidenceBasisPoints: new BN(100),
+      spreadBasisPoints: new BN(0),
       globalShortSize: new BN(0),
+      globalShortAveragePrice: new BN(0),
       netProtocolLiabilities: new BN(0),
     })
 
@@ -307,7 +527,7 @@ describe('jmx', () => {
       availableAssetInputData,
       {
         accounts: {
-          exchangeAdmin: exchangeAdmin.publicKey,
+          assetManager: exchangeAdmin.publicKey,
           exchangeAuthority: exchangeAuthorityPda, 
           exchange: exchangePda,
           mint: fakeWSolMint,
@@ -326,16 +546,12 @@ describe('jmx', () => {
 
    tx = await program.rpc.updateAssetWhitelist(
       exchangeName,
-      [fakeUsdcMint, fakeWSolMint],
-      [usdcOraclePubkey, wSolOraclePubkey],
+      wSolSeed,
       {
         accounts: {
-          exchangeAdmin: exchangeAdmin.publicKey,
+          assetManager: exchangeAdmin.publicKey,
           exchange: exchangePda,
-          //System stuff
-          systemProgram: anchor.web3.SystemProgram.programId,
-          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          availableAsset: availableAssetPdaWSol,
         },
         signers: [
           exchangeAdmin
@@ -349,27 +565,77 @@ describe('jmx', () => {
     const exchangeAccountData = program.coder.accounts.decode('Exchange', exchangeAccount.data)
     assert.equal(exchangeAccountData.assets[0].toString(), fakeUsdcMint.toString());
     assert.equal(exchangeAccountData.assets[1].toString(), fakeWSolMint.toString())
+    assert.equal(exchangeAccountData.priceOracles[1].toString(), wSolOraclePubkey.toString())
 
     let availableAssetAccount = await provider.connection.getAccountInfo(
       availableAssetPdaWSol
     );
     const availableAssetAccountData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
-    assert.equal(availableAssetAccountData.tokenDecimals.toNumber(), 1);
+    assert.equal(availableAssetAccountData.tokenDecimals.toNumber(), 8);
     assert.equal(availableAssetAccountData.tokenWeight.toNumber(), 10000);
     assert.equal(availableAssetAccountData.minProfitBasisPoints.toNumber(), 1);
-    assert.equal(availableAssetAccountData.maxLptokenAmount.toNumber(), 1);
+    assert.equal(availableAssetAccountData.maxLptokenAmount.toNumber(), 100000);
+    assert.equal(availableAssetAccountData.maxUsdDebt.toNumber(), 0);
     assert.equal(availableAssetAccountData.cumulativeFundingRate.toNumber(), 0);
-    assert.equal(availableAssetAccountData.lastFundingTime.toNumber(), 0);
-    assert.equal(availableAssetAccountData.stableToken, true);
+    assert.equal(availableAssetAccountData.lastFundingTime.toNumber() > 0, true);
+    assert.equal(availableAssetAccountData.stableToken, false);
     assert.equal(availableAssetAccountData.shortableToken, true);
+    assert.equal(availableAssetAccountData.withdrawOnly, false);
+    assert.equal(availableAssetAccountData.mintPaused, false);
+    assert.equal(availableAssetAccountData.decreasePaused, false);
     assert.equal(availableAssetAccountData.oracleAddress.toString(), wSolOraclePubkey.toString());
     assert.equal(availableAssetAccountData.backupOracleAddress.toString(), wSolOraclePubkey.toString());
+    assert.equal(availableAssetAccountData.maxPriceAgeSlots.toNumber(), 10000);
+    assert.equal(availableAssetAccountData.maxPriceConfidenceBasisPoints.toNumber(), 100);
+    assert.equal(availableAssetAccountData.spreadBasisPoints.toNumber(), 0);
     assert.equal(availableAssetAccountData.globalShortSize.toNumber(), 0);
     assert.equal(availableAssetAccountData.netProtocolLiabilities.toNumber(), 0);
     assert.equal(availableAssetAccountData.mintAddress.toString(), fakeWSolMint.toString());
     assert.equal(availableAssetAccountData.poolReserves.toNumber(), 0);
   });
 
+  it('updates the weight of the wSol available asset and the total weights', async () => {
+    const provider = anchor.Provider.env()
+    anchor.setProvider(provider);
+
+    let availableAssetAccount = await provider.connection.getAccountInfo(
+      availableAssetPdaWSol
+    );
+    const assetData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
+
+    for (const tokenWeight of [5000, 10000]) {
+      assetData.tokenWeight = new BN(tokenWeight);
+      await program.rpc.updateAvailableAsset(
+        exchangeName,
+        wSolSeed,
+        assetData,
+        {
+          accounts: {
+            assetManager: exchangeAdmin.publicKey,
+            exchange: exchangePda,
+            availableAsset: availableAssetPdaWSol,
+          },
+          signers: [
+            exchangeAdmin
+          ]
+        }
+      );
+
+      let exchangeAccount = await provider.connection.getAccountInfo(
+        exchangePda
+      );
+      const exchangeAccountData = program.coder.accounts.decode('Exchange', exchangeAccount.data)
+      assert.equal(exchangeAccountData.totalWeights.toNumber(), 10000 + tokenWeight);
+
+      availableAssetAccount = await provider.connection.getAccountInfo(
+        availableAssetPdaWSol
+      );
+      const availableAssetAccountData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
+      assert.equal(availableAssetAccountData.tokenWeight.toNumber(), tokenWeight);
+      assert.equal(availableAssetAccountData.oracleAddress.toString(), wSolOraclePubkey.toString());
+    }
+  });
+
   it('mints LP with USDC for the first and second time', async () => {
     const provider = anchor.Provider.env()
     anchor.setProvider(provider);
@@ -420,6 +686,17 @@ describe('jmx', () => {
         isWritable: false,
         isSigner: false
       },
+      // backup oracle
+      {
+        pubkey: usdcOraclePubkey,
+        isWritable: false,
+        isSigner: false
+      },
+      {
+        pubkey: availableAssetPdaUsdc,
+        isWritable: false,
+        isSigner: false
+      },
       {
         pubkey: exchangeWSolPda,
         isWritable: false,
@@ -430,12 +707,24 @@ describe('jmx', () => {
         isWritable: false,
         isSigner: false
       },
+      // backup oracle
+      {
+        pubkey: wSolOraclePubkey,
+        isWritable: false,
+        isSigner: false
+      },
+      {
+        pubkey: availableAssetPdaWSol,
+        isWritable: false,
+        isSigner: false
+      },
     ]
 
     let tx = await program.rpc.mintLpToken(
       exchangeName,
       usdcSeed,
       new BN(baseUsdcMintLamports),
+      new BN(0),
       {
         accounts: {
           userAuthority: exchangeAdmin.publicKey,
@@ -477,12 +766,14 @@ describe('jmx', () => {
     // console.log("availableAssetAccountData.feeReserves", Number(availableAssetAccountData.feeReserves))
     // console.log("Number(availableAssetAccountData.poolReserves)", Number(availableAssetAccountData.poolReserves))
     // console.log("baseUsdcMintLamports * numOfDeposits", baseUsdcMintLamports * numOfDeposits)
-    assert.equal(Number(user_lp_token_account.amount), baseUsdcMintLamports * numOfDeposits);
+    // the first deposit mints one LP per USD, valued at the min price of $1 less the confidence of 20
+    assert.equal(Number(user_lp_token_account.amount), 99900);
 
     let tx2 = await program.rpc.mintLpToken(
       exchangeName,
       usdcSeed,
       new BN(baseUsdcMintLamports),
+      new BN(0),
       {
         accounts: {
           userAuthority: exchangeAdmin.publicKey,
@@ -523,8 +814,42 @@ describe('jmx', () => {
     // assert.equal(availableAssetAccountData.poolReserves.toNumber() >= estimatedPoolReserves, true);
     // assert.equal(availableAssetAccountData.feeReserves.toNumber() >= estimatedFeeReserves, true);
     // assert.equal(estimatedFeeReserves + estimatedPoolReserves, baseUsdcLamports * numOfDeposits)
-    assert.equal(Number(user_lp_token_account.amount), baseUsdcMintLamports * numOfDeposits - (baseUsdcMintLamports * (1 - fullPenaltyAndFeeMultiplier)));
-    assert.equal(Number(user_lp_token_account.amount), 199400);
+    // mints are priced at the max aum, so the oracle confidence costs a couple of lamports on top of the fee
+    assert.equal(Number(user_lp_token_account.amount), 199470);
+
+    // the usdc pool is capped at 1_000_000 lamports
+    await assert.rejects(
+      program.rpc.mintLpToken(
+        exchangeName,
+        usdcSeed,
+        new BN(10 * baseUsdcMintLamports),
+        new BN(0),
+        {
+          accounts: {
+            userAuthority: exchangeAdmin.publicKey,
+            exchangeAuthority: exchangeAuthorityPda,
+            userReserveToken: fakeUsdcAta,
+            userLpToken: lpTokenAta,
+            exchange: exchangePda,
+            exchangeReserveToken: exchangeUSDCPda,
+            lpMint: lpMintPda,
+            availableAsset: availableAssetPdaUsdc,
+            //System stuff
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [
+            exchangeAdmin
+          ],
+          remainingAccounts: remainingAccounts
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Deposit exceeds the max reserves of the asset");
+        return true;
+      }
+    );
   });
 
   it('mints LP with wSOL for the first and second time and then burns', async () => {
@@ -563,6 +888,7 @@ describe('jmx', () => {
       exchangeName,
       wSolSeed,
       new BN(baseWSolLamports),
+      new BN(0),
       {
         accounts: {
           userAuthority: exchangeAdmin.publicKey,
@@ -610,13 +936,13 @@ describe('jmx', () => {
     // console.log("exchange_wsol_token_account", Number(exchange_wsol_token_account.amount))
     assert.equal(Number(availableAssetAccountData.poolReserves) + Number(availableAssetAccountData.feeReserves), Number(exchange_wsol_token_account.amount))
     assert.equal(Number(exchange_wsol_token_account.amount), baseWSolLamports)
-    const fxRateDiff = .0030
-    assert.equal(Number(user_lp_token_account.amount), numOfDeposits * baseUsdcMintLamports - (baseUsdcMintLamports * (1 - fullPenaltyAndFeeMultiplier + fxRateDiff)));
+    assert.equal(Number(user_lp_token_account.amount), 299254);
 
     let tx2 = await program.rpc.mintLpToken(
       exchangeName,
       wSolSeed,
       new BN(baseWSolLamports),
+      new BN(0),
       {
         accounts: {
           userAuthority: exchangeAdmin.publicKey,
@@ -653,14 +979,15 @@ describe('jmx', () => {
     // console.log("second availableAssetAccountData", Number(availableAssetAccountData.poolReserves))
     // console.log("second availableAssetAccountData", Number(availableAssetAccountData.feeReserves))
     // console.log("second user_lp_token_account", Number(user_lp_token_account.amount))
-    assert.equal(availableAssetAccountData.poolReserves.toNumber(), 1997);
-    assert.equal(availableAssetAccountData.feeReserves.toNumber(), 3);
-    assert.equal(Number(user_lp_token_account.amount), 398500);
+    assert.equal(availableAssetAccountData.poolReserves.toNumber(), 1998);
+    assert.equal(availableAssetAccountData.feeReserves.toNumber(), 2);
+    assert.equal(Number(user_lp_token_account.amount), 398835);
 
     let tx3 = await program.rpc.burnLpToken(
       exchangeName,
       wSolSeed,
       new BN(70000),
+      new BN(0),
       {
         accounts: {
           userAuthority: exchangeAdmin.publicKey,
@@ -710,11 +1037,48 @@ describe('jmx', () => {
     // console.log("wSolPoolFees", wSolPoolFees)
     // console.log("wSolExchangeTokenAccount", Number(wSolExchangeTokenAccount.amount))
     // console.log("user_lp_token_account.amount", user_lp_token_account.amount)
-    assert.equal(wSolPoolReserves, 1292);
-    assert.equal(wSolPoolFees, 7);
-    assert.equal(Number(wSolExchangeTokenAccount.amount), 1299);
+    assert.equal(wSolPoolReserves, 1297);
+    assert.equal(wSolPoolFees, 4);
+    assert.equal(Number(wSolExchangeTokenAccount.amount), 1301);
     assert.equal(Number(user_lp_token_account.amount) >= 270000, true);
     assert.equal(Number(user_lp_token_account.amount) <= 330000, true);
+
+    // the lp price is the aum per whole LP token, the fees kept in the pool put it a little above $1
+    const lpPriceSimulation = await program.simulate.getLpPrice(
+      exchangeName,
+      {
+        accounts: {
+          exchange: exchangePda,
+          lpMint: lpMintPda,
+        },
+        remainingAccounts: remainingAccounts
+      }
+    );
+    const returnLog = lpPriceSimulation.raw.find((log: string) => log.startsWith(`Program return: ${program.programId}`));
+    const lpPrice = program.coder.types.decode('LpPrice', Buffer.from(returnLog.split(' ')[3], 'base64'));
+    assert.equal(lpPrice.lpSupply.toNumber(), Number(user_lp_token_account.amount));
+    assert.equal(lpPrice.minAum.toNumber(), 3293);
+    assert.equal(lpPrice.maxAum.toNumber(), 3294);
+    assert.equal(lpPrice.minPrice.toNumber(), 1001414);
+    assert.equal(lpPrice.maxPrice.toNumber(), 1001718);
+
+    // the lp price goes through the same aum checks as mints, counting USDC twice in place of wSol is rejected
+    await assert.rejects(
+      program.rpc.getLpPrice(
+        exchangeName,
+        {
+          accounts: {
+            exchange: exchangePda,
+            lpMint: lpMintPda,
+          },
+          remainingAccounts: remainingAccounts.slice(0, 4).concat(remainingAccounts.slice(0, 4))
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Asset is passed more than once");
+        return true;
+      }
+    );
   });
 
   it('trades wSol for USDC and charges the user bps for doing so', async () => {
@@ -733,11 +1097,123 @@ describe('jmx', () => {
       'confirmed'
     )
 
+    // the quote runs the swap math on copies of the available assets and returns it as return data
+    const quoteSimulation = await program.simulate.quoteSwap(
+      exchangeName,
+      usdcSeed,
+      wSolSeed,
+      new BN(baseUsdcMintLamports),
+      {
+        accounts: {
+          userAuthority: exchangeAdmin.publicKey,
+          exchangeAuthority: exchangeAuthorityPda,
+          userInputToken: fakeUsdcAta,
+          userOutputToken: fakeWSolAta,
+          exchange: exchangePda,
+          inputExchangeReserveToken: exchangeUSDCPda,
+          outputExchangeReserveToken: exchangeWSolPda,
+          inputAvailableAsset: availableAssetPdaUsdc,
+          outputAvailableAsset: availableAssetPdaWSol,
+          //System stuff
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [
+          exchangeAdmin
+        ],
+        remainingAccounts: remainingAccounts
+      }
+    );
+    const returnLog = quoteSimulation.raw.find((log: string) => log.startsWith(`Program return: ${program.programId}`));
+    const swapQuote = program.coder.types.decode('SwapQuote', Buffer.from(returnLog.split(' ')[3], 'base64'));
+    assert.equal(swapQuote.amountOut.toNumber(), 995);
+    assert.equal(swapQuote.outputPoolReserves.toNumber(), 298);
+    assert.equal(swapQuote.outputFeeReserves.toNumber(), 8);
+
+    // the swap only pays out 994 wSol lamports, so asking for the full 1000 is rejected
+    await assert.rejects(
+      program.rpc.swap(
+        exchangeName,
+        usdcSeed,
+        wSolSeed,
+        new BN(baseUsdcMintLamports),
+        new BN(1000),
+        {
+          accounts: {
+            userAuthority: exchangeAdmin.publicKey,
+            exchangeAuthority: exchangeAuthorityPda,
+            userInputToken: fakeUsdcAta,
+            userOutputToken: fakeWSolAta,
+            exchange: exchangePda,
+            inputExchangeReserveToken: exchangeUSDCPda,
+            outputExchangeReserveToken: exchangeWSolPda,
+            inputAvailableAsset: availableAssetPdaUsdc,
+            outputAvailableAsset: availableAssetPdaWSol,
+            //System stuff
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [
+            exchangeAdmin
+          ],
+          remainingAccounts: remainingAccounts
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Output is below the minimum accepted amount");
+        return true;
+      }
+    );
+
+    // delivering exactly 995 wSol lamports costs more than a single USDC lamport
+    await assert.rejects(
+      program.rpc.swapExactOut(
+        exchangeName,
+        usdcSeed,
+        wSolSeed,
+        new BN(995),
+        new BN(1),
+        {
+          accounts: {
+            userAuthority: exchangeAdmin.publicKey,
+            exchangeAuthority: exchangeAuthorityPda,
+            userInputToken: fakeUsdcAta,
+            userOutputToken: fakeWSolAta,
+            exchange: exchangePda,
+            inputExchangeReserveToken: exchangeUSDCPda,
+            outputExchangeReserveToken: exchangeWSolPda,
+            inputAvailableAsset: availableAssetPdaUsdc,
+            outputAvailableAsset: availableAssetPdaWSol,
+            //System stuff
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [
+            exchangeAdmin
+          ],
+          remainingAccounts: remainingAccounts
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Input is above the maximum accepted amount");
+        return true;
+      }
+    );
+
+    let swappedEvent;
+    const swappedListener = program.addEventListener('Swapped', (event) => {
+      swappedEvent = event;
+    });
+
     let tx2 = await program.rpc.swap(
       exchangeName,
       usdcSeed,
       wSolSeed,
       new BN(baseUsdcMintLamports),
+      new BN(995),
       {
         accounts: {
           userAuthority: exchangeAdmin.publicKey,
@@ -762,6 +1238,11 @@ describe('jmx', () => {
     );
 
     await sleep(400)
+    await program.removeEventListener(swappedListener);
+    assert.equal(swappedEvent.inputMint.toString(), fakeUsdcMint.toString());
+    assert.equal(swappedEvent.outputMint.toString(), fakeWSolMint.toString());
+    assert.equal(swappedEvent.amountIn.toNumber(), baseUsdcMintLamports);
+    assert.equal(swappedEvent.amountOut.toNumber(), 995);
 
     let wSolUserTokenAccount = await getAccount(
       publicConnection,
@@ -796,9 +1277,9 @@ describe('jmx', () => {
     // console.log("beforeUsdcUserTokenAccount.amount", Number(beforeUsdcUserTokenAccount.amount))
 
     assert.equal(Number(beforeUsdcUserTokenAccount.amount), Number(usdcUserTokenAccount.amount) + baseUsdcMintLamports)
-    assert.equal(Number(wSolExchangeTokenAccount.amount), 304)
-    assert.equal(wSolPoolReserves, 292);
-    assert.equal(wSolPoolFees, 12);
+    assert.equal(Number(wSolExchangeTokenAccount.amount), 306)
+    assert.equal(wSolPoolReserves, 298);
+    assert.equal(wSolPoolFees, 8);
     assert.equal(Number(wSolExchangeTokenAccount.amount), wSolPoolReserves + wSolPoolFees);
   })
 
@@ -807,13 +1288,14 @@ describe('jmx', () => {
     anchor.setProvider(provider);
 
     [wSolPositionPda] = await anchor.web3.PublicKey.findProgramAddress(
-      [Buffer.from(exchangeName), exchangeAdmin.publicKey.toBytes(), availableAssetPdaWSol.toBytes()],
+      [Buffer.from(exchangeName), exchangeAdmin.publicKey.toBytes(), availableAssetPdaWSol.toBytes(), Buffer.from([1])],
       program.programId
     );
 
     let tx = await program.rpc.initializePosition(
       exchangeName,
       wSolSeed,
+      true,
       {
         accounts: {
           user: exchangeAdmin.publicKey,
@@ -847,21 +1329,28 @@ describe('jmx', () => {
     anchor.setProvider(provider);
 
     [wSolPositionPda] = await anchor.web3.PublicKey.findProgramAddress(
-      [Buffer.from(exchangeName), exchangeAdmin.publicKey.toBytes(), availableAssetPdaWSol.toBytes()],
+      [Buffer.from(exchangeName), exchangeAdmin.publicKey.toBytes(), availableAssetPdaWSol.toBytes(), Buffer.from([1])],
       program.programId
     );
 
     let tx = await program.rpc.increasePosition(
       exchangeName,
       wSolSeed,
+      wSolSeed,
+      true,
+      new BN(60_000_000),
+      new BN(59_994_100),
       {
         accounts: {
           user: exchangeAdmin.publicKey,
+          userCollateralToken: fakeWSolAta,
           position: wSolPositionPda,
           availableAsset: availableAssetPdaWSol,
           exchange: exchangePda,
           exchangeAuthority: exchangeAuthorityPda,
-          collateralMint: fakeWSolMint,
+          exchangeReserveToken: exchangeWSolPda,
+          collateralAvailableAsset: availableAssetPdaWSol,
+          collateralExchangeReserveToken: exchangeWSolPda,
           //System stuff
           systemProgram: anchor.web3.SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -869,7 +1358,8 @@ describe('jmx', () => {
         },
         signers: [
           exchangeAdmin
-        ]
+        ],
+        remainingAccounts: remainingAccounts
       }
     );
 
@@ -879,7 +1369,546 @@ describe('jmx', () => {
     const exchangeAccountData = program.coder.accounts.decode('Position', positionAccount.data)
     assert.equal(exchangeAccountData.owner.toString(), exchangeAdmin.publicKey.toString());
     assert.equal(exchangeAccountData.collateralMint.toString(), fakeWSolMint.toString());
-    assert.equal(exchangeAccountData.size, 0);
+    assert.equal(exchangeAccountData.size.toNumber(), 59_994_100);
+    assert.equal(exchangeAccountData.reserveAmount.toNumber(), 59_994_100);
+    assert.equal(exchangeAccountData.averagePrice.toNumber(), 100_000_000);
+    // 0.6 wSol at $100 less a 1bps margin fee on the $59.9941 position
+    assert.equal(exchangeAccountData.collateral.toNumber(), 59_994_001);
+
+    let availableAssetAccount = await provider.connection.getAccountInfo(
+      availableAssetPdaWSol
+    );
+    let availableAssetAccountData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
+    assert.equal(availableAssetAccountData.occupiedReserves.toNumber(), 59_994_100);
+    assert.equal(availableAssetAccountData.poolReserves.toNumber(), 59_994_299);
+    assert.equal(availableAssetAccountData.feeReserves.toNumber(), 6007);
+    // the size plus the fee less the $60 of collateral is guaranteed to the long
+    assert.equal(availableAssetAccountData.netProtocolLiabilities.toNumber(), 99);
+  })
+
+  it('partially decreases a position and pays out collateral', async () => {
+    const provider = anchor.Provider.env()
+    anchor.setProvider(provider);
+
+    let tx = await program.rpc.decreasePosition(
+      exchangeName,
+      wSolSeed,
+      wSolSeed,
+      true,
+      {
+        collateralDelta: new BN(10_000_000),
+        sizeDelta: new BN(9_994_100),
+        receiver: exchangeAdmin.publicKey,
+      },
+      {
+        accounts: {
+          user: exchangeAdmin.publicKey,
+          receiverToken: fakeWSolAta,
+          position: wSolPositionPda,
+          availableAsset: availableAssetPdaWSol,
+          exchange: exchangePda,
+          exchangeAuthority: exchangeAuthorityPda,
+          exchangeReserveToken: exchangeWSolPda,
+          collateralAvailableAsset: availableAssetPdaWSol,
+          collateralExchangeReserveToken: exchangeWSolPda,
+          //System stuff
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [
+          exchangeAdmin
+        ],
+        remainingAccounts: remainingAccounts
+      }
+    );
+
+    let positionAccount = await provider.connection.getAccountInfo(
+      wSolPositionPda
+    );
+    const positionAccountData = program.coder.accounts.decode('Position', positionAccount.data)
+    assert.equal(positionAccountData.size.toNumber(), 50_000_000);
+    assert.equal(positionAccountData.reserveAmount.toNumber(), 50_000_000);
+    // the margin fee on the closed size is taken from the $10 paid out
+    assert.equal(positionAccountData.collateral.toNumber(), 49_994_001);
+
+    let availableAssetAccount = await provider.connection.getAccountInfo(
+      availableAssetPdaWSol
+    );
+    let availableAssetAccountData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
+    assert.equal(availableAssetAccountData.occupiedReserves.toNumber(), 50_000_000);
+    assert.equal(availableAssetAccountData.poolReserves.toNumber(), 49_994_299);
+    assert.equal(availableAssetAccountData.feeReserves.toNumber(), 7006);
+    assert.equal(availableAssetAccountData.netProtocolLiabilities.toNumber(), 5999);
+  })
+
+  it('opens a short on wSol with USDC collateral', async () => {
+    const provider = anchor.Provider.env()
+    anchor.setProvider(provider);
+
+    const [wSolShortPositionPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from(exchangeName), exchangeAdmin.publicKey.toBytes(), availableAssetPdaWSol.toBytes(), Buffer.from([0])],
+      program.programId
+    );
+
+    await program.rpc.initializePosition(
+      exchangeName,
+      wSolSeed,
+      false,
+      {
+        accounts: {
+          user: exchangeAdmin.publicKey,
+          position: wSolShortPositionPda,
+          availableAsset: availableAssetPdaWSol,
+          exchange: exchangePda,
+          exchangeAuthority: exchangeAuthorityPda,
+          collateralMint: fakeUsdcMint,
+          //System stuff
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+
+    await program.rpc.increasePosition(
+      exchangeName,
+      wSolSeed,
+      usdcSeed,
+      false,
+      new BN(10000),
+      new BN(100),
+      {
+        accounts: {
+          user: exchangeAdmin.publicKey,
+          userCollateralToken: fakeUsdcAta,
+          position: wSolShortPositionPda,
+          availableAsset: availableAssetPdaWSol,
+          exchange: exchangePda,
+          exchangeAuthority: exchangeAuthorityPda,
+          exchangeReserveToken: exchangeWSolPda,
+          collateralAvailableAsset: availableAssetPdaUsdc,
+          collateralExchangeReserveToken: exchangeUSDCPda,
+          //System stuff
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [
+          exchangeAdmin
+        ],
+        remainingAccounts: remainingAccounts
+      }
+    );
+
+    let positionAccount = await provider.connection.getAccountInfo(
+      wSolShortPositionPda
+    );
+    const positionAccountData = program.coder.accounts.decode('Position', positionAccount.data)
+    assert.equal(positionAccountData.isLong, false);
+    assert.equal(positionAccountData.size.toNumber(), 100);
+    // the $0.0001 of wSol is reserved in USDC
+    assert.equal(positionAccountData.reserveAmount.toNumber(), 10000);
+    assert.equal(positionAccountData.collateral.toNumber(), 100);
+
+    let availableAssetAccount = await provider.connection.getAccountInfo(
+      availableAssetPdaWSol
+    );
+    let availableAssetAccountData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
+    assert.equal(availableAssetAccountData.globalShortSize.toNumber(), 100);
+    assert.equal(availableAssetAccountData.globalShortAveragePrice.toNumber(), 100_000_000);
+  })
+
+  it('does not liquidate a healthy position', async () => {
+    const liquidator = anchor.web3.Keypair.generate();
+    await publicConnection.confirmTransaction(
+      await publicConnection.requestAirdrop(
+        liquidator.publicKey,
+        1.0 * anchor.web3.LAMPORTS_PER_SOL
+      ),
+      "confirmed"
+    );
+    const liquidatorWSolAta = await createAssociatedTokenAccount(
+      publicConnection,
+      liquidator,
+      fakeWSolMint,
+      liquidator.publicKey
+    );
+
+    await assert.rejects(
+      program.rpc.liquidatePosition(
+        exchangeName,
+        wSolSeed,
+        wSolSeed,
+        true,
+        {
+          accounts: {
+            liquidator: liquidator.publicKey,
+            liquidatorToken: liquidatorWSolAta,
+            positionOwner: exchangeAdmin.publicKey,
+            position: wSolPositionPda,
+            availableAsset: availableAssetPdaWSol,
+            exchange: exchangePda,
+            exchangeAuthority: exchangeAuthorityPda,
+            exchangeReserveToken: exchangeWSolPda,
+            collateralAvailableAsset: availableAssetPdaWSol,
+            collateralExchangeReserveToken: exchangeWSolPda,
+            //System stuff
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [
+            liquidator
+          ],
+          remainingAccounts: remainingAccounts
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Position cannot be liquidated");
+        return true;
+      }
+    );
+  })
+
+  it('closes a position and pays out the remaining collateral', async () => {
+    const provider = anchor.Provider.env()
+    anchor.setProvider(provider);
+
+    await program.rpc.closePosition(
+      exchangeName,
+      wSolSeed,
+      wSolSeed,
+      true,
+      exchangeAdmin.publicKey,
+      {
+        accounts: {
+          user: exchangeAdmin.publicKey,
+          receiverToken: fakeWSolAta,
+          position: wSolPositionPda,
+          availableAsset: availableAssetPdaWSol,
+          exchange: exchangePda,
+          exchangeAuthority: exchangeAuthorityPda,
+          exchangeReserveToken: exchangeWSolPda,
+          collateralAvailableAsset: availableAssetPdaWSol,
+          collateralExchangeReserveToken: exchangeWSolPda,
+          //System stuff
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [
+          exchangeAdmin
+        ],
+        remainingAccounts: remainingAccounts
+      }
+    );
+
+    let positionAccount = await provider.connection.getAccountInfo(
+      wSolPositionPda
+    );
+    assert.equal(positionAccount, null);
+
+    let availableAssetAccount = await provider.connection.getAccountInfo(
+      availableAssetPdaWSol
+    );
+    let availableAssetAccountData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
+    assert.equal(availableAssetAccountData.occupiedReserves.toNumber(), 0);
+    // the collateral less the 1bps margin fee on the $50 left is paid out
+    assert.equal(availableAssetAccountData.poolReserves.toNumber(), 298);
+    assert.equal(availableAssetAccountData.feeReserves.toNumber(), 12006);
+    assert.equal(availableAssetAccountData.netProtocolLiabilities.toNumber(), 0);
+  })
+
+  it('withdraws usdc fees to the treasury and leaves the lp share in the pool', async () => {
+    await program.rpc.setTreasury(
+      exchangeName,
+      exchangeAdmin.publicKey,
+      new BN(3000),
+      {
+        accounts: {
+          feeManager: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+
+    let availableAssetAccount = await program.provider.connection.getAccountInfo(
+      availableAssetPdaUsdc
+    );
+    let availableAssetAccountData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
+    const feeReserves = availableAssetAccountData.feeReserves.toNumber();
+    const poolReserves = availableAssetAccountData.poolReserves.toNumber();
+    const treasuryAmount = Math.floor(feeReserves * 3000 / 10000);
+    assert.equal(feeReserves > 0, true);
+
+    const beforeTreasuryTokenAccount = await getAccount(
+      publicConnection,
+      fakeUsdcAta,
+      'confirmed'
+    )
+
+    await program.rpc.withdrawFees(
+      exchangeName,
+      usdcSeed,
+      {
+        accounts: {
+          feeManager: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+          exchangeAuthority: exchangeAuthorityPda,
+          availableAsset: availableAssetPdaUsdc,
+          exchangeReserveToken: exchangeUSDCPda,
+          treasuryToken: fakeUsdcAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+
+    const treasuryTokenAccount = await getAccount(
+      publicConnection,
+      fakeUsdcAta,
+      'confirmed'
+    )
+    assert.equal(Number(treasuryTokenAccount.amount), Number(beforeTreasuryTokenAccount.amount) + treasuryAmount);
+
+    availableAssetAccount = await program.provider.connection.getAccountInfo(
+      availableAssetPdaUsdc
+    );
+    availableAssetAccountData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
+    assert.equal(availableAssetAccountData.feeReserves.toNumber(), 0);
+    assert.equal(availableAssetAccountData.poolReserves.toNumber(), poolReserves + feeReserves - treasuryAmount);
+  })
+
+  it('rejects usdc mints while the exchange or usdc mints are paused', async () => {
+    await program.rpc.setPaused(
+      exchangeName,
+      true,
+      {
+        accounts: {
+          pauser: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+
+    await assert.rejects(
+      program.rpc.mintLpToken(
+        exchangeName,
+        usdcSeed,
+        new BN(baseUsdcMintLamports),
+        new BN(0),
+        {
+          accounts: {
+            userAuthority: exchangeAdmin.publicKey,
+            exchangeAuthority: exchangeAuthorityPda,
+            userReserveToken: fakeUsdcAta,
+            userLpToken: lpTokenAta,
+            exchange: exchangePda,
+            exchangeReserveToken: exchangeUSDCPda,
+            lpMint: lpMintPda,
+            availableAsset: availableAssetPdaUsdc,
+            //System stuff
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [
+            exchangeAdmin
+          ],
+          remainingAccounts: remainingAccounts
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Exchange is paused");
+        return true;
+      }
+    );
+
+    await program.rpc.setPaused(
+      exchangeName,
+      false,
+      {
+        accounts: {
+          pauser: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+    await program.rpc.setAssetPaused(
+      exchangeName,
+      usdcSeed,
+      {
+        mintPaused: true,
+        burnPaused: false,
+        swapInPaused: false,
+        swapOutPaused: false,
+        increasePaused: false,
+        decreasePaused: false,
+      },
+      {
+        accounts: {
+          pauser: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+          availableAsset: availableAssetPdaUsdc,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+
+    await assert.rejects(
+      program.rpc.mintLpToken(
+        exchangeName,
+        usdcSeed,
+        new BN(baseUsdcMintLamports),
+        new BN(0),
+        {
+          accounts: {
+            userAuthority: exchangeAdmin.publicKey,
+            exchangeAuthority: exchangeAuthorityPda,
+            userReserveToken: fakeUsdcAta,
+            userLpToken: lpTokenAta,
+            exchange: exchangePda,
+            exchangeReserveToken: exchangeUSDCPda,
+            lpMint: lpMintPda,
+            availableAsset: availableAssetPdaUsdc,
+            //System stuff
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [
+            exchangeAdmin
+          ],
+          remainingAccounts: remainingAccounts
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Action is paused for the asset");
+        return true;
+      }
+    );
+
+    await program.rpc.setAssetPaused(
+      exchangeName,
+      usdcSeed,
+      {
+        mintPaused: false,
+        burnPaused: false,
+        swapInPaused: false,
+        swapOutPaused: false,
+        increasePaused: false,
+        decreasePaused: false,
+      },
+      {
+        accounts: {
+          pauser: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+          availableAsset: availableAssetPdaUsdc,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+  })
+
+  it('delists wSol, rejecting new mints and removal while reserves remain', async () => {
+    await program.rpc.setWithdrawOnly(
+      exchangeName,
+      wSolSeed,
+      true,
+      {
+        accounts: {
+          assetManager: exchangeAdmin.publicKey,
+          exchange: exchangePda,
+          availableAsset: availableAssetPdaWSol,
+        },
+        signers: [
+          exchangeAdmin
+        ]
+      }
+    );
+
+    let availableAssetAccount = await program.provider.connection.getAccountInfo(
+      availableAssetPdaWSol
+    );
+    const availableAssetAccountData = program.coder.accounts.decode('AvailableAsset', availableAssetAccount.data)
+    assert.equal(availableAssetAccountData.withdrawOnly, true);
+
+    await assert.rejects(
+      program.rpc.mintLpToken(
+        exchangeName,
+        wSolSeed,
+        new BN(baseWSolLamports),
+        new BN(0),
+        {
+          accounts: {
+            userAuthority: exchangeAdmin.publicKey,
+            exchangeAuthority: exchangeAuthorityPda,
+            userReserveToken: fakeWSolAta,
+            userLpToken: lpTokenAta,
+            exchange: exchangePda,
+            exchangeReserveToken: exchangeWSolPda,
+            lpMint: lpMintPda,
+            availableAsset: availableAssetPdaWSol,
+            //System stuff
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [
+            exchangeAdmin
+          ],
+          remainingAccounts: remainingAccounts
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Asset is withdraw only");
+        return true;
+      }
+    );
+
+    await assert.rejects(
+      program.rpc.removeAvailableAsset(
+        exchangeName,
+        wSolSeed,
+        {
+          accounts: {
+            assetManager: exchangeAdmin.publicKey,
+            exchange: exchangePda,
+            exchangeAuthority: exchangeAuthorityPda,
+            availableAsset: availableAssetPdaWSol,
+            exchangeReserveToken: exchangeWSolPda,
+            assetManagerToken: fakeWSolAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          signers: [
+            exchangeAdmin
+          ]
+        }
+      ),
+      (err: any) => {
+        assert.equal(err.msg, "Asset still has reserves or open positions");
+        return true;
+      }
+    );
   })
 });
 